@@ -0,0 +1,411 @@
+//! Sound synthesis and WAV files, without needing a window or audio device
+//! (see a.ggez.audio for playing sounds)
+use crate::rterr;
+use crate::ArgSpec;
+use crate::Handle;
+use crate::NativeModule;
+use crate::Result;
+use crate::Value;
+
+mod synth;
+mod wav;
+
+pub use synth::*;
+pub use wav::*;
+
+pub const NAME: &str = "a.audio";
+
+pub(super) fn new() -> NativeModule {
+    NativeModule::new(NAME, |m| {
+        m.dep("a.bytes", None, &[]);
+        m.func(
+            "note_frequency",
+            ["note"],
+            concat!(
+                "Returns the frequency in Hz of the given note, either a name like ",
+                "'A4', 'C#3' or 'Bb2', or a MIDI note number (where 69 is A4, at 440 Hz)",
+            ),
+            |_globals, args, _| {
+                let mut args = args.into_iter();
+                let note = match args.next().unwrap() {
+                    Value::String(name) => note_number(name.str())? as f32,
+                    note => note.f32()?,
+                };
+                Ok(midi_frequency(note).into())
+            },
+        );
+        m.func(
+            "note_number",
+            ["name"],
+            "Returns the MIDI note number of a note name like 'A4' (69)",
+            |_globals, args, _| {
+                let mut args = args.into_iter();
+                let name = args.next().unwrap().into_string()?;
+                Ok((note_number(name.str())? as f64).into())
+            },
+        );
+        m.class::<Samples, _>("Samples", |cls| {
+            cls.doc(concat!(
+                "A buffer of float samples in [-1, 1], for synthesizing sounds.\n",
+                "Methods return new buffers instead of modifying this one, ",
+                "and the result can be saved with write_wav, or played by passing ",
+                "it to a.ggez.audio.SoundData.from_samples",
+            ));
+            cls.sfunc(
+                "tone",
+                ArgSpec::builder()
+                    .def("wave", "sine")
+                    .def("frequency", 440)
+                    .def("duration", 1)
+                    .def("amp", 1)
+                    .def("sample_rate", 44100)
+                    .def("duty", 0.5)
+                    .def("seed", 0),
+                concat!(
+                    "Generates a mono tone.\n",
+                    "wave is one of 'sine', 'square', 'saw', 'triangle' or 'noise'. ",
+                    "duty is the fraction of each period a square wave is high, ",
+                    "and seed determines the noise, so that it is reproducible",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let wave = args.next().unwrap().into_string()?;
+                    let frequency = args.next().unwrap().f32()?;
+                    let duration = args.next().unwrap().f32()?;
+                    let amp = args.next().unwrap().f32()?;
+                    let sample_rate = sample_rate_from_value(args.next().unwrap())?;
+                    let duty = args.next().unwrap().f32()?;
+                    let seed = args.next().unwrap().usize()? as u64;
+                    let wave = Wave::from_name(wave.str(), duty, seed)?;
+                    let samples = Samples::tone(wave, frequency, duration, amp, sample_rate)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "silence",
+                ArgSpec::builder()
+                    .req("duration")
+                    .def("sample_rate", 44100)
+                    .def("channels", 1),
+                "",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let duration = args.next().unwrap().f32()?;
+                    let sample_rate = sample_rate_from_value(args.next().unwrap())?;
+                    let channels = args.next().unwrap().u16()?;
+                    let samples = Samples::silence(duration, sample_rate, channels)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "from_list",
+                ArgSpec::builder()
+                    .req("samples")
+                    .def("sample_rate", 44100)
+                    .def("channels", 1),
+                "Creates a buffer from a list of (interleaved) float samples",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let data = samples_from_value(args.next().unwrap())?
+                        .into_iter()
+                        .map(|sample| sample as f32)
+                        .collect();
+                    let sample_rate = sample_rate_from_value(args.next().unwrap())?;
+                    let channels = args.next().unwrap().u16()?;
+                    let samples = Samples::new(sample_rate, channels, data)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "mix",
+                ArgSpec::builder().req("buffers").def("gains", ()),
+                concat!(
+                    "Adds up the given buffers, optionally each scaled by the ",
+                    "corresponding gain. The result is as long as the longest buffer",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let handles = samples_handles_from_value(args.next().unwrap())?;
+                    let gains = match args.next().unwrap() {
+                        Value::Nil => vec![1.0; handles.len()],
+                        gains => samples_from_value(gains)?
+                            .into_iter()
+                            .map(|gain| gain as f32)
+                            .collect(),
+                    };
+                    if gains.len() != handles.len() {
+                        return Err(rterr!(
+                            "Got {} buffers, but {} gains",
+                            handles.len(),
+                            gains.len()
+                        ));
+                    }
+                    let buffers: Vec<_> = handles.iter().map(|handle| handle.borrow()).collect();
+                    let pairs: Vec<_> = buffers.iter().map(|b| &**b).zip(gains).collect();
+                    let samples = Samples::mix(&pairs)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "concat",
+                ["buffers"],
+                "Plays the given buffers one after another",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let handles = samples_handles_from_value(args.next().unwrap())?;
+                    let buffers: Vec<_> = handles.iter().map(|handle| handle.borrow()).collect();
+                    let buffers: Vec<_> = buffers.iter().map(|b| &**b).collect();
+                    let samples = Samples::concat(&buffers)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc("sample_rate", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().sample_rate() as usize).into())
+            });
+            cls.ifunc("channels", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().channels() as usize).into())
+            });
+            cls.ifunc(
+                "frames",
+                [],
+                "The number of samples per channel",
+                |owner, _globals, _, _| Ok(owner.borrow().frames().into()),
+            );
+            cls.ifunc(
+                "duration",
+                [],
+                "The duration in seconds",
+                |owner, _globals, _, _| Ok(owner.borrow().duration().into()),
+            );
+            cls.ifunc(
+                "peak",
+                [],
+                "The largest absolute sample value",
+                |owner, _globals, _, _| Ok(owner.borrow().peak().into()),
+            );
+            cls.ifunc(
+                "rms",
+                [],
+                "The root mean square of the samples, a measure of loudness",
+                |owner, _globals, _, _| Ok(owner.borrow().rms().into()),
+            );
+            cls.ifunc(
+                "channel",
+                ["channel"],
+                "Returns the samples of one channel, as a mono buffer",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let channel = args.next().unwrap().u16()?;
+                    let samples = owner.borrow().channel(channel)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "to_mono",
+                [],
+                "Averages all channels into one",
+                |owner, globals, _, _| {
+                    let samples = owner.borrow().to_mono();
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "resample",
+                ["sample_rate"],
+                "Converts to the given sample rate, using linear interpolation",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let sample_rate = sample_rate_from_value(args.next().unwrap())?;
+                    let samples = owner.borrow().resample(sample_rate)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc("list", [], "", |owner, _globals, _, _| {
+                let list: Vec<Value> = owner
+                    .borrow()
+                    .data()
+                    .iter()
+                    .map(|sample| Value::from(*sample))
+                    .collect();
+                Ok(list.into())
+            });
+            cls.ifunc(
+                "slice",
+                ArgSpec::builder().req("start").def("end", ()),
+                "Returns the part between start and end (in seconds)",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let start = args.next().unwrap().f32()?;
+                    let end = match args.next().unwrap() {
+                        Value::Nil => None,
+                        end => Some(end.f32()?),
+                    };
+                    let samples = owner.borrow().slice(start, end);
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc("gain", ["gain"], "", |owner, globals, args, _| {
+                let mut args = args.into_iter();
+                let gain = args.next().unwrap().f32()?;
+                let samples = owner.borrow().gain(gain);
+                Ok(globals.new_handle::<Samples>(samples)?.into())
+            });
+            cls.ifunc(
+                "normalize",
+                ArgSpec::builder().def("peak", 1),
+                "Scales the samples so that the loudest one has the given amplitude",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let peak = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().normalize(peak);
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "envelope",
+                ArgSpec::builder()
+                    .def("attack", 0.01)
+                    .def("decay", 0.1)
+                    .def("sustain", 0.7)
+                    .def("release", 0.2),
+                concat!(
+                    "Applies an ADSR envelope: the volume rises from 0 to 1 over ",
+                    "'attack' seconds, falls to the 'sustain' level over 'decay' ",
+                    "seconds, and fades out over the last 'release' seconds ",
+                    "(sounds shorter than the release fade out all the way through)",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let attack = args.next().unwrap().f32()?;
+                    let decay = args.next().unwrap().f32()?;
+                    let sustain = args.next().unwrap().f32()?;
+                    let release = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().envelope(attack, decay, sustain, release)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "low_pass",
+                ["cutoff"],
+                "One-pole low-pass filter with the given cutoff frequency in Hz",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let cutoff = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().low_pass(cutoff)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "high_pass",
+                ["cutoff"],
+                "One-pole high-pass filter with the given cutoff frequency in Hz",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let cutoff = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().high_pass(cutoff)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "echo",
+                ArgSpec::builder().req("delay").def("decay", 0.5),
+                concat!(
+                    "Adds echoes delayed by 'delay' seconds, each 'decay' times ",
+                    "as loud as the previous one, extending the buffer so that ",
+                    "the echoes can fade out",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let delay = args.next().unwrap().f32()?;
+                    let decay = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().echo(delay, decay)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "pan",
+                ArgSpec::builder().def("pan", 0),
+                "Turns a mono buffer into a stereo one, with pan from -1 (left) to 1 (right)",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let pan = args.next().unwrap().f32()?;
+                    let samples = owner.borrow().pan(pan)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "from_wav",
+                ["bytes"],
+                "Decodes WAV file contents",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let bytes = args.next().unwrap().convert::<Vec<u8>>(globals)?;
+                    let samples = decode_wav(&bytes)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.sfunc(
+                "read_wav",
+                ["path"],
+                "Reads a WAV file",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let path = args.next().unwrap().into_string()?;
+                    let bytes = std::fs::read(path.str())?;
+                    let samples = decode_wav(&bytes)?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc(
+                "to_wav",
+                ArgSpec::builder().def("format", "i16"),
+                concat!(
+                    "Encodes as WAV file contents. format is one of 'i8', 'i16', ",
+                    "'i24', 'i32' or 'f32'. Samples outside [-1, 1] are clipped",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let format = args.next().unwrap().into_string()?;
+                    let bytes = owner.borrow().to_wav(format.str())?;
+                    Ok(globals.new_handle::<Vec<u8>>(bytes)?.into())
+                },
+            );
+            cls.ifunc(
+                "write_wav",
+                ArgSpec::builder().req("path").def("format", "i16"),
+                "Writes a WAV file, with a sample format as in to_wav",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let path = args.next().unwrap().into_string()?;
+                    let format = args.next().unwrap().into_string()?;
+                    let bytes = owner.borrow().to_wav(format.str())?;
+                    std::fs::write(path.str(), bytes)?;
+                    Ok(Value::Nil)
+                },
+            );
+        });
+    })
+}
+
+pub fn samples_from_value(value: Value) -> Result<Vec<f64>> {
+    let list = value.into_list()?;
+    let samples: Vec<Value> = list.borrow().iter().cloned().collect();
+    samples.into_iter().map(|sample| sample.f64()).collect()
+}
+
+fn samples_handles_from_value(value: Value) -> Result<Vec<Handle<Samples>>> {
+    let list = value.into_list()?;
+    let buffers: Vec<Value> = list.borrow().iter().cloned().collect();
+    buffers
+        .into_iter()
+        .map(|buffer| buffer.into_handle::<Samples>())
+        .collect()
+}
+
+pub fn sample_rate_from_value(value: Value) -> Result<u32> {
+    let sample_rate = value.usize()?;
+    if sample_rate > u32::MAX as usize {
+        return Err(rterr!("Sample rate {} is too large", sample_rate));
+    }
+    Ok(sample_rate as u32)
+}
@@ -0,0 +1,515 @@
+//! Procedural sound synthesis
+//!
+//! Sounds are built up as Samples (float samples in [-1, 1]), which are only
+//! encoded (e.g. as WAV, or as a.ggez.audio.SoundData) at the end, so that
+//! whole buffers can be generated, shaped and mixed natively
+use super::encode_wav;
+use super::SampleSpec;
+use crate::rterr;
+use crate::ConvertValue;
+use crate::Result;
+use std::f32::consts::PI;
+
+/// Waveforms that Samples::tone can generate
+#[derive(Clone, Copy)]
+pub enum Wave {
+    Sine,
+    /// A square wave with the given duty cycle (the fraction of each
+    /// period spent high)
+    Square(f32),
+    Saw,
+    Triangle,
+    /// White noise, from the given seed
+    Noise(u64),
+}
+
+impl Wave {
+    pub fn from_name(name: &str, duty: f32, seed: u64) -> Result<Self> {
+        match name {
+            "sine" => Ok(Wave::Sine),
+            "square" => {
+                if duty <= 0.0 || duty >= 1.0 {
+                    return Err(rterr!("Duty cycle must be between 0 and 1"));
+                }
+                Ok(Wave::Square(duty))
+            }
+            "saw" => Ok(Wave::Saw),
+            "triangle" => Ok(Wave::Triangle),
+            "noise" => Ok(Wave::Noise(seed)),
+            _ => Err(rterr!(
+                "Unrecognized wave {:?} (expected one of \
+                 'sine', 'square', 'saw', 'triangle' or 'noise')",
+                name
+            )),
+        }
+    }
+}
+
+/// A buffer of float samples, interleaved if there is more than one channel
+#[derive(Clone)]
+pub struct Samples {
+    sample_rate: u32,
+    channels: u16,
+    data: Vec<f32>,
+}
+
+impl Samples {
+    pub fn new(sample_rate: u32, channels: u16, data: Vec<f32>) -> Result<Self> {
+        if sample_rate == 0 {
+            return Err(rterr!("Sample rate must be positive"));
+        }
+        if channels == 0 {
+            return Err(rterr!("Channel count must be positive"));
+        }
+        if data.len() % channels as usize != 0 {
+            return Err(rterr!(
+                "Got {} samples, which is not a multiple of the channel count {}",
+                data.len(),
+                channels
+            ));
+        }
+        Ok(Self {
+            sample_rate,
+            channels,
+            data,
+        })
+    }
+    pub fn silence(duration: f32, sample_rate: u32, channels: u16) -> Result<Self> {
+        let frames = duration_to_frames(duration, sample_rate)?;
+        Self::new(sample_rate, channels, vec![0.0; frames * channels as usize])
+    }
+
+    /// Generates a mono tone of the given frequency and duration
+    pub fn tone(
+        wave: Wave,
+        frequency: f32,
+        duration: f32,
+        amp: f32,
+        sample_rate: u32,
+    ) -> Result<Self> {
+        let frames = duration_to_frames(duration, sample_rate)?;
+        let mut data = Vec::with_capacity(frames);
+        let mut rng = match wave {
+            Wave::Noise(seed) => XorShift::new(seed),
+            _ => XorShift::new(0),
+        };
+        for i in 0..frames {
+            // the phase, in periods (in f64, as f32 is too coarse for long sounds)
+            let phase = (i as f64 * frequency as f64 / sample_rate as f64).fract() as f32;
+            let sample = match wave {
+                Wave::Sine => (phase * 2.0 * PI).sin(),
+                Wave::Square(duty) => {
+                    if phase < duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Wave::Saw => 2.0 * phase - 1.0,
+                Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Wave::Noise(_) => rng.next_f32() * 2.0 - 1.0,
+            };
+            data.push(sample * amp);
+        }
+        Self::new(sample_rate, 1, data)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+    /// The number of frames (samples per channel)
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.channels as usize
+    }
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    /// The largest absolute sample value
+    pub fn peak(&self) -> f32 {
+        self.data.iter().fold(0.0f32, |max, s| max.max(s.abs()))
+    }
+
+    /// The root mean square of the samples, a measure of loudness
+    pub fn rms(&self) -> f32 {
+        if self.data.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.data.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+        (sum / self.data.len() as f64).sqrt() as f32
+    }
+
+    /// Returns the samples of one channel
+    pub fn channel(&self, channel: u16) -> Result<Samples> {
+        if channel >= self.channels {
+            return Err(rterr!(
+                "Channel {} out of bounds ({} channels)",
+                channel,
+                self.channels
+            ));
+        }
+        let data = self
+            .data
+            .iter()
+            .skip(channel as usize)
+            .step_by(self.channels as usize)
+            .cloned()
+            .collect();
+        Samples::new(self.sample_rate, 1, data)
+    }
+
+    /// Averages all channels into one
+    pub fn to_mono(&self) -> Samples {
+        let channels = self.channels as usize;
+        Samples {
+            sample_rate: self.sample_rate,
+            channels: 1,
+            data: self
+                .data
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect(),
+        }
+    }
+
+    /// Converts to the given sample rate, using linear interpolation
+    pub fn resample(&self, sample_rate: u32) -> Result<Samples> {
+        if sample_rate == 0 {
+            return Err(rterr!("Sample rate must be positive"));
+        }
+        let channels = self.channels as usize;
+        let frames = self.frames();
+        let new_frames = (frames as f64 * sample_rate as f64 / self.sample_rate as f64).round();
+        let new_frames = new_frames as usize;
+        let step = self.sample_rate as f64 / sample_rate as f64;
+        let mut data = Vec::with_capacity(new_frames * channels);
+        for i in 0..new_frames {
+            let pos = i as f64 * step;
+            let j = (pos as usize).min(frames.saturating_sub(1));
+            let k = (j + 1).min(frames - 1);
+            let frac = (pos - j as f64).min(1.0) as f32;
+            for c in 0..channels {
+                let a = self.data[j * channels + c];
+                let b = self.data[k * channels + c];
+                data.push(a + (b - a) * frac);
+            }
+        }
+        Samples::new(sample_rate, self.channels, data)
+    }
+
+    fn check_compatible(&self, other: &Samples) -> Result<()> {
+        if self.sample_rate != other.sample_rate || self.channels != other.channels {
+            return Err(rterr!(
+                "Cannot combine samples at {} Hz with {} channel(s) and \
+                 samples at {} Hz with {} channel(s)",
+                self.sample_rate,
+                self.channels,
+                other.sample_rate,
+                other.channels
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds up the given buffers, each scaled by its gain. The result is as
+    /// long as the longest buffer. All buffers must have the same sample rate
+    /// and channel count
+    pub fn mix(buffers: &[(&Samples, f32)]) -> Result<Self> {
+        let first = match buffers.first() {
+            Some((first, _)) => *first,
+            None => return Err(rterr!("Nothing to mix")),
+        };
+        let len = buffers.iter().map(|(b, _)| b.data.len()).max().unwrap();
+        let mut data = vec![0.0; len];
+        for (buffer, gain) in buffers {
+            first.check_compatible(buffer)?;
+            for (out, sample) in data.iter_mut().zip(&buffer.data) {
+                *out += sample * gain;
+            }
+        }
+        Self::new(first.sample_rate, first.channels, data)
+    }
+
+    /// Plays the given buffers one after another
+    pub fn concat(buffers: &[&Samples]) -> Result<Self> {
+        let first = match buffers.first() {
+            Some(first) => *first,
+            None => return Err(rterr!("Nothing to concatenate")),
+        };
+        let mut data = Vec::new();
+        for buffer in buffers {
+            first.check_compatible(buffer)?;
+            data.extend_from_slice(&buffer.data);
+        }
+        Self::new(first.sample_rate, first.channels, data)
+    }
+
+    /// Returns the part between start and end, in seconds
+    pub fn slice(&self, start: f32, end: Option<f32>) -> Samples {
+        let frames = self.frames();
+        let start = seconds_to_frames(start, self.sample_rate).min(frames);
+        let end = match end {
+            Some(end) => seconds_to_frames(end, self.sample_rate).min(frames),
+            None => frames,
+        }
+        .max(start);
+        let channels = self.channels as usize;
+        Samples {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            data: self.data[start * channels..end * channels].to_vec(),
+        }
+    }
+
+    pub fn gain(&self, gain: f32) -> Samples {
+        self.map_frames(|_, sample| sample * gain)
+    }
+
+    /// Scales the samples so that the loudest one has the given amplitude
+    pub fn normalize(&self, peak: f32) -> Samples {
+        let max = self.peak();
+        if max == 0.0 {
+            self.clone()
+        } else {
+            self.gain(peak / max)
+        }
+    }
+
+    /// Applies an ADSR envelope: the volume rises linearly from 0 to 1 over
+    /// 'attack' seconds, falls to the 'sustain' level over 'decay' seconds,
+    /// and fades out to 0 over the last 'release' seconds of the buffer.
+    /// The release scales down the rest of the envelope rather than
+    /// replacing it, so buffers shorter than the release are quieter,
+    /// but not silent
+    pub fn envelope(&self, attack: f32, decay: f32, sustain: f32, release: f32) -> Result<Samples> {
+        for (name, seconds) in &[("attack", attack), ("decay", decay), ("release", release)] {
+            if !(*seconds >= 0.0) || !seconds.is_finite() {
+                return Err(rterr!(
+                    "Envelope {} must be a non-negative number of seconds, but got {}",
+                    name,
+                    seconds
+                ));
+            }
+        }
+        if !sustain.is_finite() {
+            return Err(rterr!(
+                "Envelope sustain must be finite, but got {}",
+                sustain
+            ));
+        }
+        let rate = self.sample_rate as f32;
+        let duration = self.duration();
+        let level = move |t: f32| {
+            if t < attack {
+                t / attack
+            } else if t < attack + decay {
+                1.0 - (1.0 - sustain) * (t - attack) / decay
+            } else {
+                sustain
+            }
+        };
+        Ok(self.map_frames(move |frame, sample| {
+            let t = frame as f32 / rate;
+            let gain = if t + release < duration {
+                level(t)
+            } else {
+                level(t) * (duration - t) / release
+            };
+            sample * gain.max(0.0)
+        }))
+    }
+
+    /// One-pole low-pass filter with the given cutoff frequency
+    pub fn low_pass(&self, cutoff: f32) -> Result<Samples> {
+        check_cutoff(cutoff)?;
+        let dt = 1.0 / self.sample_rate as f32;
+        let rc = 1.0 / (2.0 * PI * cutoff);
+        let alpha = dt / (rc + dt);
+        let mut out = self.clone();
+        let channels = self.channels as usize;
+        for c in 0..channels {
+            let mut prev = 0.0;
+            for i in (c..out.data.len()).step_by(channels) {
+                prev += alpha * (out.data[i] - prev);
+                out.data[i] = prev;
+            }
+        }
+        Ok(out)
+    }
+
+    /// One-pole high-pass filter with the given cutoff frequency
+    pub fn high_pass(&self, cutoff: f32) -> Result<Samples> {
+        check_cutoff(cutoff)?;
+        let dt = 1.0 / self.sample_rate as f32;
+        let rc = 1.0 / (2.0 * PI * cutoff);
+        let alpha = rc / (rc + dt);
+        let mut out = self.clone();
+        let channels = self.channels as usize;
+        for c in 0..channels {
+            let (mut prev_in, mut prev_out) = (0.0, 0.0);
+            for i in (c..out.data.len()).step_by(channels) {
+                let input = out.data[i];
+                prev_out = alpha * (prev_out + input - prev_in);
+                prev_in = input;
+                out.data[i] = prev_out;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Adds echoes delayed by 'delay' seconds, each 'decay' times as loud
+    /// as the previous one. The buffer is extended so that the echoes can
+    /// fade out (until they are quieter than -60 dB)
+    pub fn echo(&self, delay: f32, decay: f32) -> Result<Samples> {
+        if decay < 0.0 || decay >= 1.0 {
+            return Err(rterr!("Echo decay must be in [0, 1)"));
+        }
+        let channels = self.channels as usize;
+        let delay = duration_to_frames(delay, self.sample_rate)? * channels;
+        if delay == 0 {
+            return Err(rterr!("Echo delay must be at least one sample"));
+        }
+        let repeats = if decay == 0.0 {
+            0
+        } else {
+            ((0.001f32).ln() / decay.ln()).ceil().min(64.0) as usize
+        };
+        let mut data = self.data.clone();
+        data.resize(self.data.len() + delay * repeats, 0.0);
+        for i in delay..data.len() {
+            data[i] += data[i - delay] * decay;
+        }
+        Samples::new(self.sample_rate, self.channels, data)
+    }
+
+    /// Turns a mono buffer into a stereo one, with pan from -1 (left)
+    /// to 1 (right), using constant power panning
+    pub fn pan(&self, pan: f32) -> Result<Samples> {
+        if self.channels != 1 {
+            return Err(rterr!("Only mono samples can be panned"));
+        }
+        let angle = (pan.max(-1.0).min(1.0) + 1.0) * PI / 4.0;
+        let (left, right) = (angle.cos(), angle.sin());
+        let mut data = Vec::with_capacity(self.data.len() * 2);
+        for sample in &self.data {
+            data.push(sample * left);
+            data.push(sample * right);
+        }
+        Samples::new(self.sample_rate, 2, data)
+    }
+
+    /// Encodes as a WAV file with the given sample format, clipping any
+    /// samples outside of [-1, 1]
+    pub fn to_wav(&self, format: &str) -> Result<Vec<u8>> {
+        let spec = SampleSpec::new(self.sample_rate, self.channels, format)?;
+        let (_, max) = spec.range();
+        let samples: Vec<f64> = self
+            .data
+            .iter()
+            .map(|sample| (sample.max(-1.0).min(1.0) as f64) * max)
+            .collect();
+        encode_wav(&samples, spec)
+    }
+
+    fn map_frames<F: Fn(usize, f32) -> f32>(&self, f: F) -> Samples {
+        let channels = self.channels as usize;
+        Samples {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            data: self
+                .data
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| f(i / channels, *sample))
+                .collect(),
+        }
+    }
+}
+
+impl ConvertValue for Samples {}
+
+fn seconds_to_frames(seconds: f32, sample_rate: u32) -> usize {
+    (seconds.max(0.0) * sample_rate as f32).round() as usize
+}
+
+/// The longest buffer that can be generated, in seconds
+const MAX_DURATION: f32 = 3600.0;
+
+/// Like seconds_to_frames, but for the length of a buffer to allocate,
+/// so that NaN, infinite or absurdly long durations are errors
+fn duration_to_frames(seconds: f32, sample_rate: u32) -> Result<usize> {
+    if !seconds.is_finite() || seconds > MAX_DURATION {
+        return Err(rterr!(
+            "Duration must be finite and at most {} seconds, but got {}",
+            MAX_DURATION,
+            seconds
+        ));
+    }
+    Ok(seconds_to_frames(seconds, sample_rate))
+}
+
+fn check_cutoff(cutoff: f32) -> Result<()> {
+    if !(cutoff > 0.0) || !cutoff.is_finite() {
+        return Err(rterr!(
+            "Cutoff frequency must be positive, but got {}",
+            cutoff
+        ));
+    }
+    Ok(())
+}
+
+/// The frequency of the given MIDI note number (69 is A4, at 440 Hz)
+pub fn midi_frequency(note: f32) -> f32 {
+    440.0 * 2f32.powf((note - 69.0) / 12.0)
+}
+
+/// Parses a note name like 'A4', 'C#3' or 'Bb-1' into a MIDI note number
+pub fn note_number(name: &str) -> Result<i32> {
+    let mut chars = name.chars();
+    let base = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return Err(rterr!("Invalid note name {:?}", name)),
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let octave: i32 = match octave.parse() {
+        Ok(octave) => octave,
+        Err(_) => return Err(rterr!("Invalid octave in note name {:?}", name)),
+    };
+    Ok((octave + 1) * 12 + base + accidental)
+}
+
+/// Small, seedable generator for noise, so that generated sounds
+/// are reproducible
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck at 0
+        XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
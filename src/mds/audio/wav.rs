@@ -0,0 +1,138 @@
+use super::Samples;
+use crate::mtry;
+use crate::rterr;
+use crate::Result;
+use std::io::Cursor;
+
+/// The sample formats samples can be encoded as
+const FORMATS: &[(&str, u16, hound::SampleFormat)] = &[
+    ("i8", 8, hound::SampleFormat::Int),
+    ("i16", 16, hound::SampleFormat::Int),
+    ("i24", 24, hound::SampleFormat::Int),
+    ("i32", 32, hound::SampleFormat::Int),
+    ("f32", 32, hound::SampleFormat::Float),
+];
+
+/// Describes raw PCM samples: how many per second (per channel),
+/// how many channels are interleaved, and the format of each sample
+#[derive(Clone, Copy)]
+pub struct SampleSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub sample_format: hound::SampleFormat,
+}
+
+impl Default for SampleSpec {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            channels: 1,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        }
+    }
+}
+
+impl SampleSpec {
+    /// format is one of 'i8', 'i16', 'i24', 'i32' or 'f32'
+    pub fn new(sample_rate: u32, channels: u16, format: &str) -> Result<Self> {
+        if sample_rate == 0 {
+            return Err(rterr!("Sample rate must be positive"));
+        }
+        if channels == 0 {
+            return Err(rterr!("Channel count must be positive"));
+        }
+        match FORMATS.iter().find(|(name, _, _)| *name == format) {
+            Some((_, bits_per_sample, sample_format)) => Ok(Self {
+                sample_rate,
+                channels,
+                bits_per_sample: *bits_per_sample,
+                sample_format: *sample_format,
+            }),
+            None => Err(rterr!(
+                "Unrecognized sample format {:?} (expected one of {:?})",
+                format,
+                FORMATS.iter().map(|(name, _, _)| *name).collect::<Vec<_>>()
+            )),
+        }
+    }
+    fn wav_spec(&self) -> hound::WavSpec {
+        hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: self.bits_per_sample,
+            sample_format: self.sample_format,
+        }
+    }
+    /// The range of valid sample values
+    pub(super) fn range(&self) -> (f64, f64) {
+        match self.sample_format {
+            hound::SampleFormat::Float => (-1.0, 1.0),
+            hound::SampleFormat::Int => {
+                let max = ((1i64 << (self.bits_per_sample - 1)) - 1) as f64;
+                (-max - 1.0, max)
+            }
+        }
+    }
+}
+
+/// Encodes raw samples as a WAV file. With more than one channel, the samples
+/// are interleaved (e.g. left, right, left, right, ... for stereo).
+///
+/// Integer samples are rounded, and must fit in the spec's bit depth.
+/// Float samples are expected to be in [-1, 1], and are clamped to it
+pub fn encode_wav(data: &[f64], spec: SampleSpec) -> Result<Vec<u8>> {
+    if data.len() % spec.channels as usize != 0 {
+        return Err(rterr!(
+            "Got {} samples, which is not a multiple of the channel count {}",
+            data.len(),
+            spec.channels
+        ));
+    }
+    let (min, max) = spec.range();
+    let mut bytes = Cursor::new(Vec::<u8>::new());
+    {
+        let mut writer = mtry!(hound::WavWriter::new(&mut bytes, spec.wav_spec()));
+        for sample in data {
+            match spec.sample_format {
+                hound::SampleFormat::Float => {
+                    mtry!(writer.write_sample(sample.max(min).min(max) as f32));
+                }
+                hound::SampleFormat::Int => {
+                    let sample = sample.round();
+                    if sample < min || sample > max {
+                        return Err(rterr!(
+                            "Sample {} does not fit in {} bits",
+                            sample,
+                            spec.bits_per_sample
+                        ));
+                    }
+                    mtry!(writer.write_sample(sample as i32));
+                }
+            }
+        }
+        mtry!(writer.finalize());
+    }
+    Ok(bytes.into_inner())
+}
+
+/// Decodes a WAV file into float samples in [-1, 1]
+pub fn decode_wav(bytes: &[u8]) -> Result<Samples> {
+    let mut reader = mtry!(hound::WavReader::new(Cursor::new(bytes)));
+    let spec = reader.spec();
+    let data = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|sample| Ok(mtry!(sample)))
+            .collect::<Result<Vec<_>>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| Ok(mtry!(sample) as f32 / scale))
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+    Samples::new(spec.sample_rate, spec.channels, data)
+}
@@ -0,0 +1,678 @@
+//! Color utilities shared by the graphics libraries
+//!
+//! A color value can be given as
+//!   * a Color object,
+//!   * a hex string ('#f80', '#f808', '#ff8800' or '#ff880080'),
+//!   * a CSS color name (e.g. 'rebeccapurple', or 'transparent'),
+//!   * a list of 3 or 4 numbers in the range [0, 1], or
+//!   * a list of 3 integers in the range [0, 255], optionally followed by
+//!     an alpha in [0, 1] (e.g. [255, 136, 0] or [255, 136, 0, 0.5])
+//!
+//! A list is only read as 0-255 if r, g and b are all integers and at least
+//! one of them is greater than 1, so e.g. [1, 0, 0] is still pure red in
+//! [0, 1]. For 0-255 colors that might be that dark, use Color.from_rgba8.
+use crate::rterr;
+use crate::ArgSpec;
+use crate::Error;
+use crate::NativeModule;
+use crate::Result;
+use crate::Value;
+use std::convert::TryFrom;
+
+pub const NAME: &str = "a.color";
+
+pub(super) fn new() -> NativeModule {
+    NativeModule::new(NAME, |m| {
+        m.class::<Color, _>("Color", |cls| {
+            cls.doc(concat!(
+                "An RGBA color with components in the range [0, 1]\n",
+                "Accepted anywhere a color is expected, both by a.ggez.graphics ",
+                "and by the a.canvas libraries",
+            ));
+            cls.sfunc(
+                "__call",
+                ArgSpec::builder()
+                    .req("r")
+                    .def("g", ())
+                    .def("b", ())
+                    .def("a", 1),
+                concat!(
+                    "Color(r, g, b, a=1) creates a color from components in [0, 1]\n",
+                    "Color(value) converts any color value (hex string, name, list) ",
+                    "into a Color",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let r = args.next().unwrap();
+                    let g = args.next().unwrap();
+                    let color = if g.is_nil() {
+                        Color::try_from(r)?
+                    } else {
+                        let r = r.f32()?;
+                        let g = g.f32()?;
+                        let b = args.next().unwrap().f32()?;
+                        let a = args.next().unwrap().f32()?;
+                        Color::new(r, g, b, a)
+                    };
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.sfunc(
+                "from_rgba8",
+                ArgSpec::builder()
+                    .req("r")
+                    .def("g", ())
+                    .def("b", ())
+                    .def("a", 255),
+                concat!(
+                    "Creates a color from components in [0, 255], given either ",
+                    "separately or as a single list [r, g, b] or [r, g, b, a]",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let r = args.next().unwrap();
+                    let g = args.next().unwrap();
+                    let color = if g.is_nil() {
+                        let components = r
+                            .into_list()?
+                            .borrow()
+                            .iter()
+                            .map(|x| x.f32())
+                            .collect::<Result<Vec<_>>>()?;
+                        match components.as_slice() {
+                            [r, g, b] => Color::from_rgba8(*r, *g, *b, 255.0),
+                            [r, g, b, a] => Color::from_rgba8(*r, *g, *b, *a),
+                            _ => {
+                                return Err(rterr!(
+                                    "Expected a list of 3 or 4 components, but got {}",
+                                    components.len()
+                                ))
+                            }
+                        }
+                    } else {
+                        let r = r.f32()?;
+                        let g = g.f32()?;
+                        let b = args.next().unwrap().f32()?;
+                        let a = args.next().unwrap().f32()?;
+                        Color::from_rgba8(r, g, b, a)
+                    };
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.sfunc(
+                "from_hsv",
+                ArgSpec::builder().req("h").req("s").req("v").def("a", 1),
+                "Hue is in degrees, saturation and value are in [0, 1]",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let h = args.next().unwrap().f32()?;
+                    let s = args.next().unwrap().f32()?;
+                    let v = args.next().unwrap().f32()?;
+                    let a = args.next().unwrap().f32()?;
+                    let color = Color::from_hsv(h, s, v, a);
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.sfunc(
+                "from_hsl",
+                ArgSpec::builder().req("h").req("s").req("l").def("a", 1),
+                "Hue is in degrees, saturation and lightness are in [0, 1]",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let h = args.next().unwrap().f32()?;
+                    let s = args.next().unwrap().f32()?;
+                    let l = args.next().unwrap().f32()?;
+                    let a = args.next().unwrap().f32()?;
+                    let color = Color::from_hsl(h, s, l, a);
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.ifunc("r", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().r as f64).into())
+            });
+            cls.ifunc("g", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().g as f64).into())
+            });
+            cls.ifunc("b", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().b as f64).into())
+            });
+            cls.ifunc("a", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().a as f64).into())
+            });
+            cls.ifunc(
+                "rgba",
+                [],
+                "Returns the components as a list [r, g, b, a] in [0, 1]",
+                |owner, _globals, _, _| {
+                    let c = owner.borrow();
+                    Ok(floats_to_value(&[c.r, c.g, c.b, c.a]))
+                },
+            );
+            cls.ifunc(
+                "rgba8",
+                [],
+                "Returns the components as a list [r, g, b, a] in [0, 255]",
+                |owner, _globals, _, _| {
+                    let [r, g, b, a] = owner.borrow().to_rgba8();
+                    Ok(vec![
+                        Value::from(r as i64),
+                        Value::from(g as i64),
+                        Value::from(b as i64),
+                        Value::from(a as i64),
+                    ]
+                    .into())
+                },
+            );
+            cls.ifunc(
+                "hsv",
+                [],
+                "Returns [h, s, v, a], with the hue in degrees",
+                |owner, _globals, _, _| {
+                    let c = owner.borrow();
+                    let (h, s, v) = c.to_hsv();
+                    Ok(floats_to_value(&[h, s, v, c.a]))
+                },
+            );
+            cls.ifunc(
+                "hsl",
+                [],
+                "Returns [h, s, l, a], with the hue in degrees",
+                |owner, _globals, _, _| {
+                    let c = owner.borrow();
+                    let (h, s, l) = c.to_hsl();
+                    Ok(floats_to_value(&[h, s, l, c.a]))
+                },
+            );
+            cls.ifunc(
+                "hex",
+                [],
+                "Returns the color as '#rrggbb', or '#rrggbbaa' if not opaque",
+                |owner, _globals, _, _| Ok(owner.borrow().to_hex().into()),
+            );
+            cls.ifunc(
+                "css",
+                [],
+                "Returns the color as a CSS 'rgba(...)' string",
+                |owner, _globals, _, _| Ok(owner.borrow().to_css().into()),
+            );
+            cls.ifunc("with_alpha", ["a"], "", |owner, globals, args, _| {
+                let mut args = args.into_iter();
+                let a = args.next().unwrap().f32()?;
+                let color = Color {
+                    a,
+                    ..*owner.borrow()
+                };
+                globals.new_handle(color).map(From::from)
+            });
+            cls.ifunc(
+                "lerp",
+                ["other", "t"],
+                concat!(
+                    "Linearly interpolates between this color (t = 0) ",
+                    "and other (t = 1), component-wise",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let other = Color::try_from(args.next().unwrap())?;
+                    let t = args.next().unwrap().f32()?;
+                    let color = owner.borrow().lerp(&other, t);
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.ifunc(
+                "lighten",
+                ["amount"],
+                "Increases the HSL lightness by the given amount, clamped to [0, 1]",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let amount = args.next().unwrap().f32()?;
+                    let color = owner.borrow().lighten(amount);
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.ifunc(
+                "darken",
+                ["amount"],
+                "Decreases the HSL lightness by the given amount, clamped to [0, 1]",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let amount = args.next().unwrap().f32()?;
+                    let color = owner.borrow().lighten(-amount);
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+            cls.ifunc(
+                "premultiplied",
+                [],
+                "Returns the color with r, g and b multiplied by alpha",
+                |owner, globals, _, _| {
+                    let color = owner.borrow().premultiplied();
+                    globals.new_handle(color).map(From::from)
+                },
+            );
+        });
+        m.func(
+            "css",
+            ["color"],
+            concat!(
+                "Converts a color value into a string usable as a CSS color\n",
+                "Strings are returned unchanged, so that any CSS color ",
+                "(e.g. 'hsl(...)') can still be used",
+            ),
+            |_globals, args, _| {
+                let mut args = args.into_iter();
+                match args.next().unwrap() {
+                    Value::String(string) => Ok(Value::String(string)),
+                    value => Ok(Color::try_from(value)?.to_css().into()),
+                }
+            },
+        );
+        m.func(
+            "names",
+            [],
+            "Returns the list of all recognized color names",
+            |_globals, _, _| {
+                let mut names = NAMED_COLORS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .chain(std::iter::once(TRANSPARENT))
+                    .collect::<Vec<_>>();
+                names.sort();
+                Ok(names
+                    .into_iter()
+                    .map(Value::from)
+                    .collect::<Vec<_>>()
+                    .into())
+            },
+        );
+    })
+}
+
+fn floats_to_value(xs: &[f32]) -> Value {
+    xs.iter()
+        .map(|x| Value::from(*x as f64))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_rgba8(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::new(r / 255.0, g / 255.0, b / 255.0, a / 255.0)
+    }
+
+    pub fn from_rgb_u32(rgb: u32) -> Self {
+        Self::from_rgba8(
+            ((rgb >> 16) & 0xff) as f32,
+            ((rgb >> 8) & 0xff) as f32,
+            (rgb & 0xff) as f32,
+            255.0,
+        )
+    }
+
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let f = |x: f32| (x.max(0.0).min(1.0) * 255.0).round() as u8;
+        [f(self.r), f(self.g), f(self.b), f(self.a)]
+    }
+
+    /// Parses a hex string ('#rgb', '#rgba', '#rrggbb' or '#rrggbbaa')
+    /// or a CSS color name
+    pub fn parse(string: &str) -> Option<Self> {
+        if string.starts_with('#') {
+            return Self::parse_hex(&string[1..]);
+        }
+        let name = string.to_ascii_lowercase();
+        if name == TRANSPARENT {
+            return Some(Self::new(0.0, 0.0, 0.0, 0.0));
+        }
+        NAMED_COLORS
+            .binary_search_by_key(&name.as_str(), |(name, _)| name)
+            .ok()
+            .map(|i| Self::from_rgb_u32(NAMED_COLORS[i].1))
+    }
+
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() as f32;
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32;
+        match hex.len() {
+            3 | 4 => {
+                let a = if hex.len() == 4 { digit(3) } else { 15.0 };
+                Some(Self::from_rgba8(
+                    digit(0) * 17.0,
+                    digit(1) * 17.0,
+                    digit(2) * 17.0,
+                    a * 17.0,
+                ))
+            }
+            6 | 8 => {
+                let a = if hex.len() == 8 { byte(6) } else { 255.0 };
+                Some(Self::from_rgba8(byte(0), byte(2), byte(4), a))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+
+    pub fn to_css(&self) -> String {
+        let [r, g, b, _] = self.to_rgba8();
+        format!("rgba({},{},{},{})", r, g, b, self.a.max(0.0).min(1.0))
+    }
+
+    /// Hue is in degrees in [0, 360), saturation and value in [0, 1]
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Self {
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+        Self::new(r + m, g + m, b + m, a)
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (h, s, max)
+    }
+
+    /// Hue is in degrees in [0, 360), saturation and lightness in [0, 1]
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c / 2.0;
+        Self::new(r + m, g + m, b + m, a)
+    }
+
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (h, s, l)
+    }
+
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let Self { r, g, b, .. } = *self;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (h.rem_euclid(360.0), max, min)
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let f = |x: f32, y: f32| x + (y - x) * t;
+        Self::new(
+            f(self.r, other.r),
+            f(self.g, other.g),
+            f(self.b, other.b),
+            f(self.a, other.a),
+        )
+    }
+
+    /// Adjusts the HSL lightness by the given amount
+    /// (negative amounts darken the color)
+    pub fn lighten(&self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, (l + amount).max(0.0).min(1.0), self.a)
+    }
+
+    pub fn premultiplied(&self) -> Self {
+        Self::new(self.r * self.a, self.g * self.a, self.b * self.a, self.a)
+    }
+}
+
+/// Given a hue and chroma, returns the (r, g, b) components
+/// before the lightness/value offset is added
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
+
+impl TryFrom<Value> for Color {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::String(string) => match Color::parse(&string) {
+                Some(color) => Ok(color),
+                None => Err(rterr!("Unrecognized color {:?}", string)),
+            },
+            Value::List(list) => {
+                let components = list
+                    .borrow()
+                    .iter()
+                    .map(|x| x.f32())
+                    .collect::<Result<Vec<_>>>()?;
+                let (rgb, a) = match components.as_slice() {
+                    [r, g, b] => ([*r, *g, *b], 1.0),
+                    [r, g, b, a] => ([*r, *g, *b], *a),
+                    _ => {
+                        return Err(rterr!(
+                            "Expected a color list of 3 or 4 components, but got {}",
+                            components.len()
+                        ))
+                    }
+                };
+                let rgba8 = rgb.iter().any(|x| *x > 1.0);
+                let scale = if !rgba8 {
+                    1.0
+                } else if rgb.iter().all(|x| x.fract() == 0.0) {
+                    255.0
+                } else {
+                    return Err(rterr!(
+                        "Color components must be in [0, 1], or integers in [0, 255], \
+                         but got {:?}",
+                        rgb
+                    ));
+                };
+                if !(0.0..=1.0).contains(&a) {
+                    return Err(rterr!("Color alpha must be in [0, 1], but got {}", a));
+                }
+                let [r, g, b] = rgb;
+                Ok(Color::new(r / scale, g / scale, b / scale, a))
+            }
+            value if value.is_handle::<Color>() => Ok(*value.into_handle::<Color>()?.borrow()),
+            value => Err(rterr!("Expected color, but got {:?}", value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Color {
+    type Error = Error;
+    fn try_from(value: &Value) -> Result<Self> {
+        TryFrom::try_from(value.clone())
+    }
+}
+
+/// The one CSS color name with a non-opaque alpha, kept out of NAMED_COLORS
+const TRANSPARENT: &str = "transparent";
+
+/// The CSS named colors, sorted by name
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
@@ -0,0 +1,47 @@
+use super::SoundData;
+use crate::mds::audio::Samples;
+use crate::mtry;
+use crate::Result;
+use rodio::Source;
+use std::io::Cursor;
+
+impl SoundData {
+    fn decoder(&self) -> Result<rodio::Decoder<Cursor<Vec<u8>>>> {
+        let bytes: &[u8] = self.get().as_ref();
+        Ok(mtry!(rodio::Decoder::new(Cursor::new(bytes.to_vec()))))
+    }
+
+    /// Decodes the sound (any format rodio supports: WAV, OGG Vorbis,
+    /// FLAC or MP3) into float samples in [-1, 1]
+    pub fn decode(&self) -> Result<Samples> {
+        let decoder = self.decoder()?;
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels();
+        let data = decoder
+            .map(|sample| sample as f32 / -(i16::MIN as f32))
+            .collect();
+        Samples::new(sample_rate, channels, data)
+    }
+
+    /// Only reads the header, without decoding any samples
+    pub fn sample_rate(&self) -> Result<u32> {
+        Ok(self.decoder()?.sample_rate())
+    }
+
+    /// Only reads the header, without decoding any samples
+    pub fn channels(&self) -> Result<u16> {
+        Ok(self.decoder()?.channels())
+    }
+
+    /// The duration in seconds. If the format doesn't record it, the
+    /// samples are counted as they are decoded, without being kept
+    pub fn duration(&self) -> Result<f64> {
+        let decoder = self.decoder()?;
+        if let Some(duration) = decoder.total_duration() {
+            return Ok(duration.as_secs_f64());
+        }
+        let sample_rate = decoder.sample_rate() as f64;
+        let channels = decoder.channels() as f64;
+        Ok(decoder.count() as f64 / channels / sample_rate)
+    }
+}
@@ -0,0 +1,181 @@
+use super::*;
+use crate::Handle;
+use ggez::audio::SoundSource;
+
+/// A volume fade in progress
+pub struct Fade {
+    from: f32,
+    to: f32,
+    elapsed: f64,
+    duration: f64,
+    stop: bool,
+    /// Cancelled fades stay registered until the next step, so that a
+    /// fade started in the meantime reuses the registration
+    cancelled: bool,
+}
+
+/// Anything whose volume can be faded
+pub trait Fadable {
+    fn fade_mut(&mut self) -> &mut Option<Fade>;
+    fn volume(&self) -> f32;
+    fn set_volume(&mut self, volume: f32);
+    fn stop(&mut self);
+}
+
+impl Fadable for Source {
+    fn fade_mut(&mut self) -> &mut Option<Fade> {
+        &mut self.1
+    }
+    fn volume(&self) -> f32 {
+        self.get().volume()
+    }
+    fn set_volume(&mut self, volume: f32) {
+        self.get_mut().set_volume(volume)
+    }
+    fn stop(&mut self) {
+        self.get_mut().stop()
+    }
+}
+
+impl Fadable for SpatialSource {
+    fn fade_mut(&mut self) -> &mut Option<Fade> {
+        &mut self.1
+    }
+    fn volume(&self) -> f32 {
+        self.get().volume()
+    }
+    fn set_volume(&mut self, volume: f32) {
+        self.get_mut().set_volume(volume)
+    }
+    fn stop(&mut self) {
+        self.get_mut().stop()
+    }
+}
+
+impl Fadable for SoundStream {
+    fn fade_mut(&mut self) -> &mut Option<Fade> {
+        &mut self.fade
+    }
+    fn volume(&self) -> f32 {
+        SoundStream::volume(self)
+    }
+    fn set_volume(&mut self, volume: f32) {
+        SoundStream::set_volume(self, volume)
+    }
+    fn stop(&mut self) {
+        SoundStream::stop(self)
+    }
+}
+
+/// Handles to everything that is currently fading
+pub enum Fading {
+    Source(Handle<Source>),
+    Spatial(Handle<SpatialSource>),
+    Stream(Handle<SoundStream>),
+}
+
+impl Fading {
+    /// Advances the fade, and returns whether it is still in progress
+    fn step(&self, dt: f64) -> bool {
+        match self {
+            Fading::Source(source) => step(&mut *source.borrow_mut(), dt),
+            Fading::Spatial(source) => step(&mut *source.borrow_mut(), dt),
+            Fading::Stream(stream) => step(&mut *stream.borrow_mut(), dt),
+        }
+    }
+}
+
+fn step<T: Fadable>(target: &mut T, dt: f64) -> bool {
+    let fade = match target.fade_mut() {
+        Some(fade) => fade,
+        None => return false,
+    };
+    if fade.cancelled {
+        *target.fade_mut() = None;
+        return false;
+    }
+    fade.elapsed += dt;
+    if fade.elapsed < fade.duration {
+        let t = (fade.elapsed / fade.duration) as f32;
+        let volume = fade.from + (fade.to - fade.from) * t;
+        target.set_volume(volume);
+        return true;
+    }
+    let fade = target.fade_mut().take().unwrap();
+    if fade.stop {
+        // Restore the volume, so that the next play isn't silent
+        target.stop();
+        target.set_volume(fade.from);
+    } else {
+        target.set_volume(fade.to);
+    }
+    false
+}
+
+/// The targets of the fades in progress, advanced every frame by the event loop
+#[derive(Default)]
+struct Fades(Vec<Fading>);
+
+/// Starts fading the target's volume to 'to' over the given number of
+/// seconds, replacing any fade already in progress on it.
+/// If 'stop' is true, the target is stopped once the fade is done,
+/// and its volume is restored
+pub fn start_fade<T: Fadable>(
+    globals: &mut Globals,
+    target: &mut T,
+    handle: impl FnOnce() -> Fading,
+    to: f32,
+    duration: f64,
+    stop: bool,
+) -> Result<()> {
+    let from = match target.fade_mut() {
+        // When replacing a fade out, restore the volume from before it
+        Some(fade) if fade.stop && !fade.cancelled => fade.from,
+        _ => target.volume(),
+    };
+    let already_fading = target.fade_mut().is_some();
+    *target.fade_mut() = Some(Fade {
+        from,
+        to,
+        elapsed: 0.0,
+        duration,
+        stop,
+        cancelled: false,
+    });
+    if !already_fading {
+        if !globals.stash().has::<Fades>() {
+            globals.stash_mut().set(Fades::default())?;
+        }
+        globals.stash_mut().get_mut::<Fades>()?.0.push(handle());
+    }
+    Ok(())
+}
+
+/// Cancels any fade in progress on the target, e.g. because its volume
+/// was set directly. A fade out's volume is restored, but the target isn't
+/// stopped
+pub fn cancel_fade<T: Fadable>(target: &mut T) {
+    let restore = match target.fade_mut() {
+        Some(fade) if !fade.cancelled => {
+            fade.cancelled = true;
+            if fade.stop {
+                Some(fade.from)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    if let Some(volume) = restore {
+        target.set_volume(volume);
+    }
+}
+
+/// Advances all fades by dt seconds
+pub(in super::super) fn step_fades(globals: &mut Globals, dt: f64) {
+    let stash = globals.stash_mut();
+    if stash.has::<Fades>() {
+        let mut fades = stash.get_mut::<Fades>().unwrap();
+        fades.0.retain(|fading| fading.step(dt));
+    }
+}
@@ -1,16 +1,170 @@
 use super::*;
+use crate::mds::audio::sample_rate_from_value;
+use crate::mds::audio::samples_from_value;
+use crate::mds::audio::SampleSpec;
+use crate::mds::audio::Samples;
 use ggez::audio::SoundSource;
 
+mod decode;
+mod fade;
 mod sound;
+mod stream;
 mod wav;
 
+pub use fade::*;
 pub use sound::*;
+pub use stream::*;
+
+/// Registers the methods shared by Source and SpatialSource
+macro_rules! source_methods {
+    ($cls:ident, $fading:path) => {
+        $cls.ifunc("set_pitch", ["pitch"], "", |owner, _globals, args, _| {
+            let mut args = args.into_iter();
+            let pitch = args.next().unwrap().f32()?;
+            owner.borrow_mut().get_mut().set_pitch(pitch);
+            Ok(Value::Nil)
+        });
+        $cls.ifunc("set_repeat", ["repeat"], "", |owner, _globals, args, _| {
+            let mut args = args.into_iter();
+            let repeat = args.next().unwrap().truthy();
+            owner.borrow_mut().get_mut().set_repeat(repeat);
+            Ok(Value::Nil)
+        });
+        $cls.ifunc("repeat", [], "", |owner, _globals, _, _| {
+            Ok(owner.borrow().get().repeat().into())
+        });
+        $cls.ifunc(
+            "play",
+            [],
+            "Plays from the start, cancelling any fade",
+            |owner, _globals, _, _| {
+                let mut source = owner.borrow_mut();
+                cancel_fade(&mut *source);
+                mtry!(source.get_mut().play());
+                Ok(Value::Nil)
+            },
+        );
+        $cls.ifunc("pause", [], "", |owner, _globals, _, _| {
+            owner.borrow().get().pause();
+            Ok(Value::Nil)
+        });
+        $cls.ifunc("resume", [], "", |owner, _globals, _, _| {
+            owner.borrow().get().resume();
+            Ok(Value::Nil)
+        });
+        $cls.ifunc(
+            "stop",
+            [],
+            "Stops, cancelling any fade",
+            |owner, _globals, _, _| {
+                let mut source = owner.borrow_mut();
+                cancel_fade(&mut *source);
+                source.get_mut().stop();
+                Ok(Value::Nil)
+            },
+        );
+        $cls.ifunc("playing", [], "", |owner, _globals, _, _| {
+            Ok(owner.borrow().get().playing().into())
+        });
+        $cls.ifunc("paused", [], "", |owner, _globals, _, _| {
+            Ok(owner.borrow().get().paused().into())
+        });
+        $cls.ifunc(
+            "elapsed",
+            [],
+            "The number of seconds played since the sound was started",
+            |owner, _globals, _, _| Ok(owner.borrow().get().elapsed().as_secs_f32().into()),
+        );
+        $cls.ifunc(
+            "set_fade_in",
+            ["seconds"],
+            "Sets how long the sound fades in for, the next time it is played",
+            |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let seconds = duration_from_value(args.next().unwrap())?;
+                owner.borrow_mut().get_mut().set_fade_in(seconds);
+                Ok(Value::Nil)
+            },
+        );
+        $cls.ifunc(
+            "set_volume",
+            ["value"],
+            "Sets the volume, cancelling any fade",
+            |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let value = args.next().unwrap().f32()?;
+                let mut source = owner.borrow_mut();
+                cancel_fade(&mut *source);
+                source.get_mut().set_volume(value);
+                Ok(Value::Nil)
+            },
+        );
+        $cls.ifunc("volume", [], "", |owner, _globals, _, _| {
+            Ok(owner.borrow().get().volume().into())
+        });
+        fade_methods!($cls, $fading);
+    };
+}
+
+/// Registers fade_to and fade_out, which are advanced by the event loop
+macro_rules! fade_methods {
+    ($cls:ident, $fading:path) => {
+        $cls.ifunc(
+            "fade_to",
+            ["volume", "seconds"],
+            concat!(
+                "Gradually changes the volume over the given number of seconds ",
+                "(as the event loop runs)",
+            ),
+            |owner, globals, args, _| {
+                let mut args = args.into_iter();
+                let volume = args.next().unwrap().f32()?;
+                let seconds = args.next().unwrap().f64()?;
+                let handle = owner.clone();
+                let mut target = owner.borrow_mut();
+                start_fade(
+                    globals,
+                    &mut *target,
+                    || $fading(handle),
+                    volume,
+                    seconds,
+                    false,
+                )?;
+                Ok(Value::Nil)
+            },
+        );
+        $cls.ifunc(
+            "fade_out",
+            ["seconds"],
+            concat!(
+                "Fades out over the given number of seconds (as the event loop runs), ",
+                "and then stops, restoring the volume for the next time it is played",
+            ),
+            |owner, globals, args, _| {
+                let mut args = args.into_iter();
+                let seconds = args.next().unwrap().f64()?;
+                let handle = owner.clone();
+                let mut target = owner.borrow_mut();
+                start_fade(
+                    globals,
+                    &mut *target,
+                    || $fading(handle),
+                    0.0,
+                    seconds,
+                    true,
+                )?;
+                Ok(Value::Nil)
+            },
+        );
+    };
+}
 
 pub const NAME: &str = "a.ggez.audio";
 
 pub(in super::super) fn new() -> NativeModule {
     NativeModule::new(NAME, |m| {
         m.dep("a.bytes", None, &[]);
+        m.dep("a.audio", None, &[]);
         m.class::<SoundData, _>("SoundData", |cls| {
             cls.sfunc("from_bytes", ["bytes"], "", |globals, args, _| {
                 let mut args = args.into_iter();
@@ -18,25 +172,62 @@ pub(in super::super) fn new() -> NativeModule {
                 let data = SoundData::from_bytes(&bytes);
                 Ok(globals.new_handle::<SoundData>(data)?.into())
             });
-            cls.sfunc("from_samples", ["samples"], "", |globals, args, _| {
-                let mut args = args.into_iter();
-                let samples = <Vec<i16>>::try_from(args.next().unwrap())?;
-                let data = SoundData::from_samples(&samples)?;
-                Ok(globals.new_handle::<SoundData>(data)?.into())
-            });
+            cls.sfunc(
+                "from_samples",
+                ArgSpec::builder()
+                    .req("samples")
+                    .def("sample_rate", 44100)
+                    .def("channels", 1)
+                    .def("format", "i16"),
+                concat!(
+                    "Creates SoundData from a list of samples, or from an a.audio.Samples ",
+                    "buffer (in which case sample_rate and channels come from the buffer, ",
+                    "and its float samples are scaled to the given format).\n",
+                    "With more than one channel, samples are interleaved ",
+                    "(e.g. left, right, left, right, ... for stereo).\n",
+                    "format is one of 'i8', 'i16', 'i24', 'i32' (integer samples, ",
+                    "which must fit in the given number of bits) or 'f32' ",
+                    "(float samples in [-1, 1])",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let samples = args.next().unwrap();
+                    let data = if samples.is_handle::<Samples>() {
+                        let samples = samples.into_handle::<Samples>()?;
+                        args.next().unwrap();
+                        args.next().unwrap();
+                        let format = args.next().unwrap().into_string()?;
+                        let samples = samples.borrow();
+                        SoundData::from_buffer(&samples, format.str())?
+                    } else {
+                        let samples = samples_from_value(samples)?;
+                        let spec = spec_from_args(&mut args)?;
+                        SoundData::from_samples(&samples, spec)?
+                    };
+                    Ok(globals.new_handle::<SoundData>(data)?.into())
+                },
+            );
             cls.sfunc(
                 "sine",
                 ArgSpec::builder()
                     .def("nsamples", 44100)
                     .def("hertz", 440)
-                    .def("amp", i16::MAX),
-                "",
+                    .def("amp", i16::MAX)
+                    .def("sample_rate", 44100)
+                    .def("channels", 1)
+                    .def("format", "i16"),
+                concat!(
+                    "Creates a sine wave of nsamples samples per channel.\n",
+                    "amp is in units of the sample format, ",
+                    "so e.g. for 'f32' it should be at most 1",
+                ),
                 |globals, args, _| {
                     let mut args = args.into_iter();
                     let nsamples = args.next().unwrap().usize()?;
-                    let hertz = args.next().unwrap().u16()?;
-                    let amp = args.next().unwrap().u16()?;
-                    let data = SoundData::sine(nsamples, hertz, amp as f32)?;
+                    let hertz = args.next().unwrap().f64()?;
+                    let amp = args.next().unwrap().f64()?;
+                    let spec = spec_from_args(&mut args)?;
+                    let data = SoundData::sine(nsamples, hertz, amp, spec)?;
                     Ok(globals.new_handle::<SoundData>(data)?.into())
                 },
             );
@@ -46,8 +237,39 @@ pub(in super::super) fn new() -> NativeModule {
                 let bytes = bytes.to_vec();
                 Ok(globals.new_handle::<Vec<u8>>(bytes)?.into())
             });
+            cls.ifunc(
+                "decode",
+                [],
+                concat!(
+                    "Decodes the sound (WAV, OGG Vorbis, FLAC or MP3) into Samples, ",
+                    "e.g. for analysis, or to get at the sample values with list()",
+                ),
+                |owner, globals, _args, _| {
+                    let samples = owner.borrow().decode()?;
+                    Ok(globals.new_handle::<Samples>(samples)?.into())
+                },
+            );
+            cls.ifunc("sample_rate", [], "", |owner, _globals, _args, _| {
+                Ok((owner.borrow().sample_rate()? as usize).into())
+            });
+            cls.ifunc("channels", [], "", |owner, _globals, _args, _| {
+                Ok((owner.borrow().channels()? as usize).into())
+            });
+            cls.ifunc(
+                "duration",
+                [],
+                concat!(
+                    "The duration in seconds. Formats that don't record it ",
+                    "(e.g. MP3) are decoded to count their samples",
+                ),
+                |owner, _globals, _args, _| Ok(owner.borrow().duration()?.into()),
+            );
         });
         m.class::<Source, _>("Source", |cls| {
+            cls.doc(concat!(
+                "Plays a SoundData. Sources can't seek, ",
+                "but SoundStream can (see also SpatialSource)",
+            ));
             cls.sfunc("from_data", ["data"], "", |globals, args, _| {
                 let mut args = args.into_iter();
                 let data = args.next().unwrap().convert::<SoundData>(globals)?;
@@ -55,42 +277,209 @@ pub(in super::super) fn new() -> NativeModule {
                 let source = Source::from_data(ctx, data)?;
                 Ok(globals.new_handle::<Source>(source)?.into())
             });
-            cls.ifunc("set_pitch", ["pitch"], "", |owner, _globals, args, _| {
-                let mut args = args.into_iter();
-                let pitch = args.next().unwrap().f32()?;
-                owner.borrow_mut().get_mut().set_pitch(pitch);
-                Ok(Value::Nil)
-            });
-            cls.ifunc("set_repeat", ["repeat"], "", |owner, _globals, args, _| {
+            source_methods!(cls, Fading::Source);
+        });
+        m.class::<SpatialSource, _>("SpatialSource", |cls| {
+            cls.doc(concat!(
+                "A Source whose volume in each ear depends on the position ",
+                "of its emitter relative to the listener's ears.\n",
+                "Positions are [x, y] or [x, y, z] lists",
+            ));
+            cls.sfunc(
+                "from_data",
+                ArgSpec::builder()
+                    .req("data")
+                    .def("position", ())
+                    .def("left_ear", ())
+                    .def("right_ear", ()),
+                "",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let data = args.next().unwrap().convert::<SoundData>(globals)?;
+                    let position = args.next().unwrap();
+                    let left_ear = args.next().unwrap();
+                    let right_ear = args.next().unwrap();
+                    let ctx = getctx(globals)?;
+                    let mut source = SpatialSource::from_data(ctx, data)?;
+                    if !position.is_nil() {
+                        source.get_mut().set_position(point3(position)?);
+                    }
+                    if !left_ear.is_nil() || !right_ear.is_nil() {
+                        let left = point3(left_ear)?;
+                        let right = point3(right_ear)?;
+                        source.get_mut().set_ears(left, right);
+                    }
+                    Ok(globals.new_handle::<SpatialSource>(source)?.into())
+                },
+            );
+            cls.ifunc(
+                "set_position",
+                ["position"],
+                "Sets the position of the emitter",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let position = point3(args.next().unwrap())?;
+                    owner.borrow_mut().get_mut().set_position(position);
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "set_ears",
+                ["left", "right"],
+                "Sets the positions of the listener's ears",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let left = point3(args.next().unwrap())?;
+                    let right = point3(args.next().unwrap())?;
+                    owner.borrow_mut().get_mut().set_ears(left, right);
+                    Ok(Value::Nil)
+                },
+            );
+            source_methods!(cls, Fading::Spatial);
+        });
+        m.class::<SoundStream, _>("SoundStream", |cls| {
+            cls.doc(concat!(
+                "Plays a sound file (WAV, OGG Vorbis, FLAC or MP3) while decoding it, ",
+                "e.g. for long music files that would take a lot of memory ",
+                "as SoundData. Unlike Source, it can seek",
+            ));
+            cls.sfunc("open", ["path"], "", |globals, args, _| {
                 let mut args = args.into_iter();
-                let repeat = args.next().unwrap().truthy();
-                owner.borrow_mut().get_mut().set_repeat(repeat);
-                Ok(Value::Nil)
-            });
-            cls.ifunc("repeat", [], "", |owner, _globals, _, _| {
-                Ok(owner.borrow().get().repeat().into())
-            });
-            cls.ifunc("play", [], "", |owner, _globals, _, _| {
-                mtry!(owner.borrow_mut().get_mut().play());
-                Ok(Value::Nil)
+                let path = args.next().unwrap().into_string()?;
+                let stream = SoundStream::open(path.str())?;
+                Ok(globals.new_handle::<SoundStream>(stream)?.into())
             });
+            cls.ifunc(
+                "play",
+                [],
+                concat!(
+                    "Plays from the start, or from where seek last moved it while ",
+                    "stopped, cancelling any fade",
+                ),
+                |owner, _globals, _, _| {
+                    let mut stream = owner.borrow_mut();
+                    cancel_fade(&mut *stream);
+                    stream.play()?;
+                    Ok(Value::Nil)
+                },
+            );
             cls.ifunc("pause", [], "", |owner, _globals, _, _| {
-                owner.borrow().get().pause();
+                owner.borrow().pause();
                 Ok(Value::Nil)
             });
             cls.ifunc("resume", [], "", |owner, _globals, _, _| {
-                owner.borrow().get().resume();
+                owner.borrow_mut().resume()?;
                 Ok(Value::Nil)
             });
-            cls.ifunc("set_volume", ["value"], "", |owner, _globals, args, _| {
+            cls.ifunc(
+                "stop",
+                [],
+                "Stops, cancelling any fade",
+                |owner, _globals, _, _| {
+                    let mut stream = owner.borrow_mut();
+                    cancel_fade(&mut *stream);
+                    stream.stop();
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("playing", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().playing().into())
+            });
+            cls.ifunc("paused", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().paused().into())
+            });
+            cls.ifunc(
+                "elapsed",
+                [],
+                "The position in seconds from the start of the file",
+                |owner, _globals, _, _| Ok(owner.borrow().elapsed().into()),
+            );
+            cls.ifunc(
+                "seek",
+                ["seconds"],
+                concat!(
+                    "Moves to the given time. Everything before it is decoded ",
+                    "(but not played), so seeking far into a long file takes a moment.\n",
+                    "If nothing is playing, only the position is recorded, and the ",
+                    "stream stays silent until play or resume is called",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let seconds = args.next().unwrap().f32()?;
+                    owner.borrow_mut().seek(seconds)?;
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("set_repeat", ["repeat"], "", |owner, _globals, args, _| {
                 let mut args = args.into_iter();
-                let value = args.next().unwrap().f32()?;
-                owner.borrow_mut().get_mut().set_volume(value);
+                let repeat = args.next().unwrap().truthy();
+                owner.borrow_mut().set_repeat(repeat);
                 Ok(Value::Nil)
             });
+            cls.ifunc("repeat", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().repeat().into())
+            });
+            cls.ifunc(
+                "set_fade_in",
+                ["seconds"],
+                "Sets how long the sound fades in for, the next time it is played",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let seconds = duration_from_value(args.next().unwrap())?;
+                    owner.borrow_mut().set_fade_in(seconds);
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "set_volume",
+                ["value"],
+                "Sets the volume, cancelling any fade",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let value = args.next().unwrap().f32()?;
+                    let mut stream = owner.borrow_mut();
+                    cancel_fade(&mut *stream);
+                    stream.set_volume(value);
+                    Ok(Value::Nil)
+                },
+            );
             cls.ifunc("volume", [], "", |owner, _globals, _, _| {
-                Ok(owner.borrow().get().volume().into())
+                Ok(owner.borrow().volume().into())
             });
+            fade_methods!(cls, Fading::Stream);
         });
     })
 }
+
+/// Reads the sample_rate, channels and format arguments
+fn spec_from_args<I: Iterator<Item = Value>>(args: &mut I) -> Result<SampleSpec> {
+    let sample_rate = sample_rate_from_value(args.next().unwrap())?;
+    let channels = args.next().unwrap().u16()?;
+    let format = args.next().unwrap().into_string()?;
+    SampleSpec::new(sample_rate, channels, format.str())
+}
+
+fn duration_from_value(value: Value) -> Result<std::time::Duration> {
+    let seconds = value.f64()?;
+    // from_secs_f64 panics on anything else
+    if !(seconds >= 0.0) || !seconds.is_finite() {
+        return Err(rterr!(
+            "Expected a finite, non-negative duration, but got {}",
+            seconds
+        ));
+    }
+    Ok(std::time::Duration::from_secs_f64(seconds))
+}
+
+fn point3(value: Value) -> Result<[f32; 3]> {
+    let list = value.into_list()?;
+    let coords: Vec<Value> = list.borrow().iter().cloned().collect();
+    match coords.len() {
+        2 => Ok([coords[0].f32()?, coords[1].f32()?, 0.0]),
+        3 => Ok([coords[0].f32()?, coords[1].f32()?, coords[2].f32()?]),
+        n => Err(rterr!(
+            "Expected a 2D or 3D position, but got {} coordinates",
+            n
+        )),
+    }
+}
@@ -1,3 +1,4 @@
+use super::Fade;
 use crate::mtry;
 use crate::ConvertValue;
 use crate::Result;
@@ -16,14 +17,14 @@ impl SoundData {
 
 impl ConvertValue for SoundData {}
 
-pub struct Source(ggez::audio::Source);
+pub struct Source(ggez::audio::Source, pub(super) Option<Fade>);
 
 impl Source {
     pub fn from_data(ctx: &mut ggez::Context, data: SoundData) -> Result<Self> {
-        Ok(Self(mtry!(ggez::audio::Source::from_data(
-            ctx,
-            data.0.clone()
-        ))))
+        Ok(Self(
+            mtry!(ggez::audio::Source::from_data(ctx, data.0.clone())),
+            None,
+        ))
     }
     pub fn get(&self) -> &ggez::audio::Source {
         &self.0
@@ -32,3 +33,22 @@ impl Source {
         &mut self.0
     }
 }
+
+/// A Source whose sound depends on the position of its emitter
+/// relative to the listener's ears
+pub struct SpatialSource(ggez::audio::SpatialSource, pub(super) Option<Fade>);
+
+impl SpatialSource {
+    pub fn from_data(ctx: &mut ggez::Context, data: SoundData) -> Result<Self> {
+        Ok(Self(
+            mtry!(ggez::audio::SpatialSource::from_data(ctx, data.0.clone())),
+            None,
+        ))
+    }
+    pub fn get(&self) -> &ggez::audio::SpatialSource {
+        &self.0
+    }
+    pub fn get_mut(&mut self) -> &mut ggez::audio::SpatialSource {
+        &mut self.0
+    }
+}
@@ -0,0 +1,211 @@
+use super::Fade;
+use crate::mtry;
+use crate::rterr;
+use crate::Result;
+use rodio::Source;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+type Decoder = rodio::Decoder<BufReader<File>>;
+
+fn open(path: &Path) -> Result<Decoder> {
+    let file = File::open(path)?;
+    Ok(mtry!(rodio::Decoder::new(BufReader::new(file))))
+}
+
+/// Plays a sound file while decoding it, so that long music files
+/// don't need to be loaded into memory all at once
+pub struct SoundStream {
+    path: PathBuf,
+    device: rodio::Device,
+    sink: rodio::Sink,
+    sample_rate: u32,
+    channels: u16,
+    /// The number of samples played (or skipped) since the start of the file
+    position: Arc<AtomicU64>,
+    repeat: Arc<AtomicBool>,
+    fade_in: Duration,
+    volume: f32,
+    started: bool,
+    /// Where to start from on the next play or resume, if seeked
+    /// while nothing was playing
+    pending_seek: Option<u64>,
+    pub(super) fade: Option<Fade>,
+}
+
+impl SoundStream {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let decoder = open(&path)?;
+        let device = match rodio::default_output_device() {
+            Some(device) => device,
+            None => return Err(rterr!("No audio output device available")),
+        };
+        let sink = rodio::Sink::new(&device);
+        Ok(Self {
+            path,
+            sample_rate: decoder.sample_rate(),
+            channels: decoder.channels(),
+            device,
+            sink,
+            position: Arc::new(AtomicU64::new(0)),
+            repeat: Arc::new(AtomicBool::new(false)),
+            fade_in: Duration::from_secs(0),
+            volume: 1.0,
+            started: false,
+            pending_seek: None,
+            fade: None,
+        })
+    }
+
+    /// Starts playing from the given position (in samples), replacing
+    /// anything that is currently playing
+    fn start(&mut self, position: u64) -> Result<()> {
+        let mut decoder = open(&self.path)?;
+        for _ in 0..position {
+            if decoder.next().is_none() {
+                break;
+            }
+        }
+        self.position.store(position, Ordering::SeqCst);
+        self.sink.stop();
+        self.sink = rodio::Sink::new(&self.device);
+        self.sink.set_volume(self.volume);
+        let reader = Reader {
+            path: self.path.clone(),
+            decoder,
+            position: self.position.clone(),
+            repeat: self.repeat.clone(),
+        };
+        if self.fade_in > Duration::from_secs(0) {
+            self.sink.append(reader.fade_in(self.fade_in));
+        } else {
+            self.sink.append(reader);
+        }
+        self.started = true;
+        self.pending_seek = None;
+        Ok(())
+    }
+
+    /// Plays from the start, or from where it was seeked to
+    /// while nothing was playing
+    pub fn play(&mut self) -> Result<()> {
+        self.start(self.pending_seek.unwrap_or(0))
+    }
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+    pub fn resume(&mut self) -> Result<()> {
+        if let Some(position) = self.pending_seek {
+            self.start(position)?;
+        }
+        self.sink.play();
+        Ok(())
+    }
+    pub fn stop(&mut self) {
+        self.sink.stop();
+        self.sink = rodio::Sink::new(&self.device);
+        self.sink.set_volume(self.volume);
+        self.position.store(0, Ordering::SeqCst);
+        self.started = false;
+        self.pending_seek = None;
+    }
+    pub fn playing(&self) -> bool {
+        self.started && !self.sink.empty() && !self.sink.is_paused()
+    }
+    pub fn paused(&self) -> bool {
+        self.started && !self.sink.empty() && self.sink.is_paused()
+    }
+
+    /// Moves to the given time, by decoding (without playing) everything
+    /// before it, so seeking far into a long file takes a moment.
+    /// If nothing is playing (never played, stopped, or finished),
+    /// only the position is recorded, for the next play or resume
+    pub fn seek(&mut self, seconds: f32) -> Result<()> {
+        let frames = (seconds.max(0.0) as f64 * self.sample_rate as f64) as u64;
+        let position = frames * self.channels as u64;
+        if !self.started || self.sink.empty() {
+            self.position.store(position, Ordering::SeqCst);
+            self.pending_seek = Some(position);
+            return Ok(());
+        }
+        let paused = self.sink.is_paused();
+        self.start(position)?;
+        if paused {
+            self.sink.pause();
+        }
+        Ok(())
+    }
+
+    /// The position in seconds from the start of the file
+    pub fn elapsed(&self) -> f32 {
+        let samples = self.position.load(Ordering::SeqCst) as f64;
+        (samples / self.channels as f64 / self.sample_rate as f64) as f32
+    }
+    pub fn repeat(&self) -> bool {
+        self.repeat.load(Ordering::SeqCst)
+    }
+    pub fn set_repeat(&mut self, repeat: bool) {
+        self.repeat.store(repeat, Ordering::SeqCst);
+    }
+    /// Sets how long the sound fades in for, the next time it is played
+    pub fn set_fade_in(&mut self, duration: Duration) {
+        self.fade_in = duration;
+    }
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.sink.set_volume(volume);
+    }
+}
+
+/// Feeds the decoded samples to the sink, keeping track of the
+/// position, and starting over at the end if repeating
+struct Reader {
+    path: PathBuf,
+    decoder: Decoder,
+    position: Arc<AtomicU64>,
+    repeat: Arc<AtomicBool>,
+}
+
+impl Iterator for Reader {
+    type Item = i16;
+    fn next(&mut self) -> Option<i16> {
+        let sample = match self.decoder.next() {
+            Some(sample) => sample,
+            None if self.repeat.load(Ordering::SeqCst) => {
+                // If the file can no longer be read, just stop
+                self.decoder = open(&self.path).ok()?;
+                self.position.store(0, Ordering::SeqCst);
+                self.decoder.next()?
+            }
+            None => return None,
+        };
+        self.position.fetch_add(1, Ordering::SeqCst);
+        Some(sample)
+    }
+}
+
+impl rodio::Source for Reader {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.decoder.current_frame_len()
+    }
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
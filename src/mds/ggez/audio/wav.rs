@@ -1,44 +1,32 @@
 use super::SoundData;
-use crate::mtry;
+use crate::mds::audio::encode_wav;
+use crate::mds::audio::SampleSpec;
+use crate::mds::audio::Samples;
 use crate::Result;
-use std::f32::consts::PI;
-use std::io::Cursor;
+use std::f64::consts::PI;
 
 impl SoundData {
-    pub fn from_samples(data: &[i16]) -> Result<Self> {
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: 44100,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        let mut bytes = Cursor::new(Vec::<u8>::new());
-        {
-            let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
-            for sample in data {
-                mtry!(writer.write_sample(*sample));
-            }
-            mtry!(writer.finalize());
-        }
-        Ok(Self::from_bytes(&bytes.into_inner()))
+    /// Creates SoundData from raw samples (see encode_wav)
+    pub fn from_samples(data: &[f64], spec: SampleSpec) -> Result<Self> {
+        Ok(Self::from_bytes(&encode_wav(data, spec)?))
     }
-    pub fn sine(nsamples: usize, hertz: u16, amp: f32) -> Result<Self> {
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: 44100,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        let mut bytes = Cursor::new(Vec::<u8>::new());
-        {
-            let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
-            for t in 0..nsamples {
-                let t = t as f32 / 44100.0;
-                let sample = (t * (hertz as f32) * 2.0 * PI).sin();
-                writer.write_sample((sample * amp) as i16).unwrap();
+
+    /// Creates SoundData from a synthesized buffer, with the given sample format
+    pub fn from_buffer(samples: &Samples, format: &str) -> Result<Self> {
+        Ok(Self::from_bytes(&samples.to_wav(format)?))
+    }
+
+    /// A sine wave of nsamples samples per channel, with the same
+    /// sample in every channel. amp is in units of the spec's format
+    pub fn sine(nsamples: usize, hertz: f64, amp: f64, spec: SampleSpec) -> Result<Self> {
+        let mut samples = Vec::with_capacity(nsamples * spec.channels as usize);
+        for t in 0..nsamples {
+            let t = t as f64 / spec.sample_rate as f64;
+            let sample = (t * hertz * 2.0 * PI).sin() * amp;
+            for _ in 0..spec.channels {
+                samples.push(sample);
             }
-            mtry!(writer.finalize());
         }
-        Ok(Self::from_bytes(&bytes.into_inner()))
+        Self::from_samples(&samples, spec)
     }
 }
@@ -0,0 +1,179 @@
+use super::*;
+
+#[derive(Clone, Copy)]
+pub struct FullscreenType(ggez::conf::FullscreenType);
+
+impl From<FullscreenType> for ggez::conf::FullscreenType {
+    fn from(x: FullscreenType) -> Self {
+        x.0
+    }
+}
+
+impl From<ggez::conf::FullscreenType> for FullscreenType {
+    fn from(x: ggez::conf::FullscreenType) -> Self {
+        Self(x)
+    }
+}
+
+impl TryFrom<Value> for FullscreenType {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let string = value.into_string()?;
+        let ft = match string.str() {
+            "windowed" => ggez::conf::FullscreenType::Windowed,
+            "true" => ggez::conf::FullscreenType::True,
+            "desktop" => ggez::conf::FullscreenType::Desktop,
+            _ => {
+                return Err(rterr!(
+                    "Expected 'windowed', 'true' or 'desktop', but got {:?}",
+                    string
+                ))
+            }
+        };
+        Ok(ft.into())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct NumSamples(ggez::conf::NumSamples);
+
+impl From<NumSamples> for ggez::conf::NumSamples {
+    fn from(x: NumSamples) -> Self {
+        x.0
+    }
+}
+
+impl TryFrom<Value> for NumSamples {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let n = value.usize()?;
+        match ggez::conf::NumSamples::from_u32(n as u32) {
+            Some(samples) => Ok(Self(samples)),
+            None => Err(rterr!(
+                "Expected 0, 1, 2, 4, 8 or 16 samples, but got {:?}",
+                n
+            )),
+        }
+    }
+}
+
+/// Window mode options, given as a map, e.g.
+/// ['width': 1024, 'height': 768, 'resizable': true]
+#[derive(Clone)]
+pub struct WindowMode(ggez::conf::WindowMode);
+
+impl From<WindowMode> for ggez::conf::WindowMode {
+    fn from(x: WindowMode) -> Self {
+        x.0
+    }
+}
+
+impl From<ggez::conf::WindowMode> for WindowMode {
+    fn from(x: ggez::conf::WindowMode) -> Self {
+        Self(x)
+    }
+}
+
+impl TryFrom<Value> for WindowMode {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let mut mode = ggez::conf::WindowMode::default();
+        match value {
+            Value::Nil => {}
+            Value::Map(map) => {
+                let mut map = map.to_string_keys()?;
+                if let Some(sizeval) = map.remove("size") {
+                    let [width, height] = <[f32; 2]>::try_from(sizeval)?;
+                    mode = mode.dimensions(width, height);
+                }
+                if let Some(widthval) = map.remove("width") {
+                    mode.width = widthval.f32()?;
+                }
+                if let Some(heightval) = map.remove("height") {
+                    mode.height = heightval.f32()?;
+                }
+                if let Some(fullscreenval) = map.remove("fullscreen") {
+                    mode.fullscreen_type = match fullscreenval {
+                        Value::Bool(true) => ggez::conf::FullscreenType::True,
+                        Value::Bool(false) => ggez::conf::FullscreenType::Windowed,
+                        value => FullscreenType::try_from(value)?.into(),
+                    };
+                }
+                if let Some(val) = map.remove("maximized") {
+                    mode.maximized = val.truthy();
+                }
+                if let Some(val) = map.remove("borderless") {
+                    mode.borderless = val.truthy();
+                }
+                if let Some(val) = map.remove("resizable") {
+                    mode.resizable = val.truthy();
+                }
+                if let Some(val) = map.remove("min_size") {
+                    let [width, height] = <[f32; 2]>::try_from(val)?;
+                    mode = mode.min_dimensions(width, height);
+                }
+                if let Some(val) = map.remove("max_size") {
+                    let [width, height] = <[f32; 2]>::try_from(val)?;
+                    mode = mode.max_dimensions(width, height);
+                }
+                if !map.is_empty() {
+                    let keys: Vec<_> = map.keys().collect();
+                    return Err(rterr!("Unused window mode attributes: {:?}", keys));
+                }
+            }
+            _ => return Err(rterr!("Expected window mode map, but got {:?}", value)),
+        }
+        Ok(mode.into())
+    }
+}
+
+/// Window setup options, given as a map, e.g.
+/// ['title': 'My Game', 'vsync': false, 'samples': 4]
+#[derive(Clone)]
+pub struct WindowSetup(ggez::conf::WindowSetup);
+
+impl From<WindowSetup> for ggez::conf::WindowSetup {
+    fn from(x: WindowSetup) -> Self {
+        x.0
+    }
+}
+
+impl From<ggez::conf::WindowSetup> for WindowSetup {
+    fn from(x: ggez::conf::WindowSetup) -> Self {
+        Self(x)
+    }
+}
+
+impl TryFrom<Value> for WindowSetup {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let mut setup = ggez::conf::WindowSetup::default();
+        match value {
+            Value::Nil => {}
+            Value::Map(map) => {
+                let mut map = map.to_string_keys()?;
+                if let Some(titleval) = map.remove("title") {
+                    setup = setup.title(titleval.into_string()?.str());
+                }
+                if let Some(val) = map.remove("vsync") {
+                    setup = setup.vsync(val.truthy());
+                }
+                if let Some(val) = map.remove("samples") {
+                    setup = setup.samples(NumSamples::try_from(val)?.into());
+                }
+                if let Some(val) = map.remove("icon") {
+                    setup = setup.icon(val.into_string()?.str());
+                }
+                if let Some(val) = map.remove("srgb") {
+                    setup = setup.srgb(val.truthy());
+                }
+                if !map.is_empty() {
+                    let keys: Vec<_> = map.keys().collect();
+                    return Err(rterr!("Unused window setup attributes: {:?}", keys));
+                }
+            }
+            _ => return Err(rterr!("Expected window setup map, but got {:?}", value)),
+        }
+        Ok(setup.into())
+    }
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+/// Frame based animation player.
+///
+/// Holds a list of src rects (e.g. from Image.frames or Atlas.region)
+/// and how long each one is shown, and advances through them as
+/// update is called with the elapsed time
+pub struct Animation {
+    frames: Vec<ggez::graphics::Rect>,
+    durations: Vec<f32>,
+    looping: bool,
+    index: usize,
+    elapsed: f32,
+    playing: bool,
+    finished: bool,
+}
+
+impl Animation {
+    pub fn new(
+        frames: Vec<ggez::graphics::Rect>,
+        durations: Vec<f32>,
+        looping: bool,
+    ) -> Result<Self> {
+        if frames.is_empty() {
+            return Err(rterr!("Animation requires at least one frame"));
+        }
+        if frames.len() != durations.len() {
+            return Err(rterr!(
+                "Got {} frames, but {} frame durations",
+                frames.len(),
+                durations.len()
+            ));
+        }
+        // Also rejects NaN
+        if durations.iter().any(|d| !(*d > 0.0)) {
+            return Err(rterr!("Frame durations must be positive"));
+        }
+        Ok(Self {
+            frames,
+            durations,
+            looping,
+            index: 0,
+            elapsed: 0.0,
+            playing: true,
+            finished: false,
+        })
+    }
+    pub fn update(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.elapsed += dt;
+        while self.elapsed >= self.durations[self.index] {
+            if self.index + 1 < self.frames.len() {
+                self.elapsed -= self.durations[self.index];
+                self.index += 1;
+            } else if self.looping {
+                self.elapsed -= self.durations[self.index];
+                self.index = 0;
+            } else {
+                self.elapsed = self.durations[self.index];
+                self.playing = false;
+                self.finished = true;
+                break;
+            }
+        }
+    }
+    pub fn frame(&self) -> ggez::graphics::Rect {
+        self.frames[self.index]
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn set_index(&mut self, index: usize) -> Result<()> {
+        if index >= self.frames.len() {
+            return Err(rterr!(
+                "Frame index {} out of bounds ({} frames)",
+                index,
+                self.frames.len()
+            ));
+        }
+        self.index = index;
+        self.elapsed = 0.0;
+        self.finished = false;
+        Ok(())
+    }
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+    pub fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+    }
+
+    /// Whether a non-looping animation has reached the end of its last frame
+    /// (and not merely been paused on it)
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.elapsed = 0.0;
+        self.playing = true;
+        self.finished = false;
+    }
+}
+
+impl ConvertValue for Animation {}
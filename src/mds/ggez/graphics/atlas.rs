@@ -0,0 +1,185 @@
+use super::*;
+use std::path::Path;
+
+/// Collects images to be packed into a single texture atlas
+pub struct AtlasBuilder {
+    padding: u32,
+    max_width: u32,
+    entries: Vec<(RcStr, image::RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new(padding: u32, max_width: u32) -> Self {
+        Self {
+            padding,
+            max_width,
+            entries: Vec::new(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn add_file<P: AsRef<Path>>(&mut self, name: RcStr, p: P) -> Result<()> {
+        let bytes = std::fs::read(p)?;
+        self.add_bytes(name, &bytes)
+    }
+    pub fn add_bytes(&mut self, name: RcStr, bytes: &[u8]) -> Result<()> {
+        let im = mtry!(image::load_from_memory(bytes)).to_rgba();
+        self.add_rgba(name, im)
+    }
+    pub fn add_rgba(&mut self, name: RcStr, im: image::RgbaImage) -> Result<()> {
+        if self.entries.iter().any(|(n, _)| *n == name) {
+            return Err(rterr!("Duplicate atlas entry {:?}", name));
+        }
+        if im.width() + 2 * self.padding > self.max_width {
+            return Err(rterr!(
+                "Atlas entry {:?} ({} pixels wide) does not fit in max_width {}",
+                name,
+                im.width(),
+                self.max_width
+            ));
+        }
+        self.entries.push((name, im));
+        Ok(())
+    }
+
+    /// Packs all added images into one Image, using simple shelf packing
+    /// (tallest images first, left to right, in rows of at most max_width)
+    pub fn build(&self, ctx: &mut ggez::Context) -> Result<Atlas> {
+        let padding = self.padding;
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse(self.entries[*i].1.height()));
+
+        let mut positions = vec![(0, 0); self.entries.len()];
+        let (mut x, mut y, mut shelf_height, mut width) = (padding, padding, 0, 0);
+        for i in order {
+            let im = &self.entries[i].1;
+            if x > padding && x + im.width() + padding > self.max_width {
+                x = padding;
+                y += shelf_height + padding;
+                shelf_height = 0;
+            }
+            positions[i] = (x, y);
+            x += im.width() + padding;
+            width = width.max(x);
+            shelf_height = shelf_height.max(im.height());
+        }
+        let height = y + shelf_height + padding;
+        let width = width.max(1);
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(rterr!("Atlas of {}x{} pixels is too large", width, height));
+        }
+
+        let mut pixels = image::RgbaImage::new(width, height);
+        let mut regions = HashMap::new();
+        for ((name, im), (x, y)) in self.entries.iter().zip(positions) {
+            image::imageops::replace(&mut pixels, im, x, y);
+            regions.insert(
+                name.clone(),
+                ggez::graphics::Rect::new(
+                    x as f32,
+                    y as f32,
+                    im.width() as f32,
+                    im.height() as f32,
+                ),
+            );
+        }
+        let image = Image::from_rgba8(ctx, width as u16, height as u16, &pixels.into_raw())?;
+        Ok(Atlas { image, regions })
+    }
+}
+
+impl ConvertValue for AtlasBuilder {}
+
+/// A packed texture atlas: one Image plus named regions of it
+pub struct Atlas {
+    image: Image,
+    regions: HashMap<RcStr, ggez::graphics::Rect>,
+}
+
+impl Atlas {
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+    pub fn names(&self) -> Vec<RcStr> {
+        let mut names: Vec<_> = self.regions.keys().cloned().collect();
+        names.sort_by(|a, b| a.str().cmp(b.str()));
+        names
+    }
+
+    /// Returns the region of the given entry, in pixels
+    pub fn pixel_region(&self, name: &RcStr) -> Result<ggez::graphics::Rect> {
+        match self.regions.get(name) {
+            Some(rect) => Ok(*rect),
+            None => Err(rterr!("Atlas entry {:?} not found", name)),
+        }
+    }
+
+    /// Returns the region of the given entry, normalized to [0, 1]
+    /// so that it can be used as the 'src' of draw or SpriteBatch.add
+    pub fn region(&self, name: &RcStr) -> Result<ggez::graphics::Rect> {
+        let rect = self.pixel_region(name)?;
+        Ok(normalize_rect(self.image.get(), rect))
+    }
+}
+
+impl ConvertValue for Atlas {}
+
+fn normalize_rect(
+    image: &ggez::graphics::Image,
+    rect: ggez::graphics::Rect,
+) -> ggez::graphics::Rect {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    ggez::graphics::Rect::new(
+        rect.x / width,
+        rect.y / height,
+        rect.w / width,
+        rect.h / height,
+    )
+}
+
+/// Slices a uniform spritesheet into frames, returned as normalized
+/// src rects, in row-major order.
+///
+/// margin is the space around the whole sheet, and spacing the space
+/// between adjacent frames (both in pixels). If count is given, only
+/// the first count frames are returned
+pub fn grid_frames(
+    image: &ggez::graphics::Image,
+    frame_width: u16,
+    frame_height: u16,
+    margin: u16,
+    spacing: u16,
+    count: Option<usize>,
+) -> Result<Vec<ggez::graphics::Rect>> {
+    if frame_width == 0 || frame_height == 0 {
+        return Err(rterr!("Frame size must be positive"));
+    }
+    let (width, height) = (image.width() as u32, image.height() as u32);
+    let (fw, fh) = (frame_width as u32, frame_height as u32);
+    let (margin, spacing) = (margin as u32, spacing as u32);
+    let mut frames = Vec::new();
+    let mut y = margin;
+    while y + fh + margin <= height {
+        let mut x = margin;
+        while x + fw + margin <= width {
+            if count == Some(frames.len()) {
+                return Ok(frames);
+            }
+            let rect = ggez::graphics::Rect::new(x as f32, y as f32, fw as f32, fh as f32);
+            frames.push(normalize_rect(image, rect));
+            x += fw + spacing;
+        }
+        y += fh + spacing;
+    }
+    if let Some(count) = count {
+        if frames.len() < count {
+            return Err(rterr!(
+                "Requested {} frames, but the sheet only has {}",
+                count,
+                frames.len()
+            ));
+        }
+    }
+    Ok(frames)
+}
@@ -0,0 +1,101 @@
+use super::*;
+
+#[derive(Clone, Copy)]
+pub struct BlendMode(ggez::graphics::BlendMode);
+
+impl From<BlendMode> for ggez::graphics::BlendMode {
+    fn from(x: BlendMode) -> Self {
+        x.0
+    }
+}
+
+impl TryFrom<Value> for BlendMode {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let string = value.into_string()?;
+        let bm = match string.str() {
+            "alpha" => ggez::graphics::BlendMode::Alpha,
+            "add" => ggez::graphics::BlendMode::Add,
+            "subtract" => ggez::graphics::BlendMode::Subtract,
+            "multiply" => ggez::graphics::BlendMode::Multiply,
+            "replace" => ggez::graphics::BlendMode::Replace,
+            "lighten" => ggez::graphics::BlendMode::Lighten,
+            "darken" => ggez::graphics::BlendMode::Darken,
+            "invert" => ggez::graphics::BlendMode::Invert,
+            _ => {
+                return Err(rterr!(
+                    concat!(
+                        "Expected 'alpha', 'add', 'subtract', 'multiply', 'replace', ",
+                        "'lighten', 'darken' or 'invert', but got {:?}",
+                    ),
+                    string
+                ))
+            }
+        };
+        Ok(Self(bm))
+    }
+}
+
+/// Drawing state that ggez does not keep track of globally
+struct DrawState {
+    blend_mode: Option<ggez::graphics::BlendMode>,
+    filter: ggez::graphics::FilterMode,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            blend_mode: None,
+            filter: ggez::graphics::FilterMode::Linear,
+        }
+    }
+}
+
+fn with_draw_state<R, F: FnOnce(&mut DrawState) -> R>(globals: &mut Globals, f: F) -> Result<R> {
+    let stash = globals.stash_mut();
+    if !stash.has::<DrawState>() {
+        stash.set(DrawState::default())?;
+    }
+    let mut state = stash.get_mut::<DrawState>()?;
+    Ok(f(&mut state))
+}
+
+pub fn blend_mode(globals: &mut Globals) -> Result<Option<ggez::graphics::BlendMode>> {
+    with_draw_state(globals, |state| state.blend_mode)
+}
+
+pub fn set_blend_mode(
+    globals: &mut Globals,
+    mode: Option<ggez::graphics::BlendMode>,
+) -> Result<()> {
+    with_draw_state(globals, |state| state.blend_mode = mode)
+}
+
+pub fn default_filter(globals: &mut Globals) -> Result<ggez::graphics::FilterMode> {
+    with_draw_state(globals, |state| state.filter)
+}
+
+pub fn set_default_filter(globals: &mut Globals, filter: ggez::graphics::FilterMode) -> Result<()> {
+    let ctx = getctx(globals)?;
+    ggez::graphics::set_default_filter(ctx, filter);
+    with_draw_state(globals, |state| state.filter = filter)
+}
+
+/// Draws the drawable, using the blend mode set with set_blend_mode (if any)
+pub fn draw_with_blend_mode(
+    ctx: &mut ggez::Context,
+    drawable: &mut dyn Drawable,
+    param: DrawParam,
+    blend_mode: Option<ggez::graphics::BlendMode>,
+) -> Result<()> {
+    if blend_mode.is_none() {
+        mtry!(drawable.draw(ctx, param));
+        return Ok(());
+    }
+    let previous = drawable.blend_mode();
+    drawable.set_blend_mode(blend_mode);
+    let r = drawable.draw(ctx, param);
+    drawable.set_blend_mode(previous);
+    mtry!(r);
+    Ok(())
+}
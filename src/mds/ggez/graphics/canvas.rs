@@ -0,0 +1,56 @@
+use super::*;
+use crate::Handle;
+
+pub struct Canvas(ggez::graphics::Canvas);
+
+impl Canvas {
+    pub fn new(
+        ctx: &mut ggez::Context,
+        width: u16,
+        height: u16,
+        samples: ggez::conf::NumSamples,
+    ) -> Result<Self> {
+        Ok(Self(mtry!(ggez::graphics::Canvas::new(
+            ctx, width, height, samples
+        ))))
+    }
+    pub fn with_window_size(ctx: &mut ggez::Context) -> Result<Self> {
+        Ok(Self(mtry!(ggez::graphics::Canvas::with_window_size(ctx))))
+    }
+    pub fn get(&self) -> &ggez::graphics::Canvas {
+        &self.0
+    }
+    pub fn get_mut(&mut self) -> &mut ggez::graphics::Canvas {
+        &mut self.0
+    }
+}
+
+impl ConvertValue for Canvas {}
+
+/// Keeps track of the canvas currently being rendered to
+/// (None means that we are rendering to the screen)
+struct CurrentCanvas(Option<Handle<Canvas>>);
+
+pub(super) fn current_canvas(globals: &Globals) -> Option<Handle<Canvas>> {
+    let stash = globals.stash();
+    if stash.has::<CurrentCanvas>() {
+        stash.get::<CurrentCanvas>().unwrap().0.clone()
+    } else {
+        None
+    }
+}
+
+pub(super) fn set_canvas(globals: &mut Globals, canvas: Option<Handle<Canvas>>) -> Result<()> {
+    let ctx = getctx(globals)?;
+    match &canvas {
+        Some(canvas) => ggez::graphics::set_canvas(ctx, Some(canvas.borrow().get())),
+        None => ggez::graphics::set_canvas(ctx, None),
+    }
+    let stash = globals.stash_mut();
+    if stash.has::<CurrentCanvas>() {
+        stash.get_mut::<CurrentCanvas>()?.0 = canvas;
+    } else {
+        stash.set(CurrentCanvas(canvas))?;
+    }
+    Ok(())
+}
@@ -14,19 +14,15 @@ impl From<Color> for ggez::graphics::Color {
     }
 }
 
+/// Accepts any value accepted by a.color (Color objects, hex strings,
+/// CSS color names, and lists of floats or 0-255 integers)
 impl TryFrom<Value> for Color {
     type Error = Error;
     fn try_from(value: Value) -> Result<Color> {
-        match &value {
-            Value::List(list) if list.borrow().len() == 3 => {
-                let [r, g, b] = <[f32; 3]>::try_from(value)?;
-                Ok(Color((r, g, b).into()))
-            }
-            _ => {
-                let [r, g, b, a] = <[f32; 4]>::try_from(value)?;
-                Ok(Color((r, g, b, a).into()))
-            }
-        }
+        let color = crate::mds::color::Color::try_from(value)?;
+        Ok(Color(ggez::graphics::Color::new(
+            color.r, color.g, color.b, color.a,
+        )))
     }
 }
 
@@ -38,16 +34,22 @@ impl TryFrom<&Value> for Color {
 }
 
 #[derive(Clone)]
-pub struct Text(ggez::graphics::Text);
+pub struct Text(pub(super) ggez::graphics::Text, pub(super) TextLayout);
 
 impl Text {
     pub fn get(&self) -> &ggez::graphics::Text {
         &self.0
     }
+    pub fn get_mut(&mut self) -> &mut ggez::graphics::Text {
+        &mut self.0
+    }
 }
 
 impl ConvertValue for Text {
     fn convert(globals: &mut Globals, value: &Value) -> Result<Text> {
+        if value.is_handle::<Text>() {
+            return Ok(Text::clone(&value.to_xref::<Text>(globals)?));
+        }
         let mut fragments = Vec::new();
         to_fragments(globals, value, &mut fragments)?;
         let mut text = ggez::graphics::Text::default();
@@ -76,7 +78,7 @@ fn to_fragments(
 
 impl From<ggez::graphics::Text> for Text {
     fn from(x: ggez::graphics::Text) -> Self {
-        Self(x)
+        Self(x, TextLayout::default())
     }
 }
 
@@ -156,4 +158,44 @@ impl From<Font> for ggez::graphics::Font {
     }
 }
 
+impl Font {
+    pub fn from_file<P: AsRef<std::path::Path>>(ctx: &mut ggez::Context, p: P) -> Result<Font> {
+        let bytes = std::fs::read(p)?;
+        Self::from_bytes(ctx, &bytes)
+    }
+    pub fn from_bytes(ctx: &mut ggez::Context, bytes: &[u8]) -> Result<Font> {
+        Ok(Self(mtry!(ggez::graphics::Font::new_glyph_font_bytes(
+            ctx, bytes
+        ))))
+    }
+}
+
 impl ConvertValue for Font {}
+
+#[derive(Clone, Copy)]
+pub struct Align(ggez::graphics::Align);
+
+impl From<Align> for ggez::graphics::Align {
+    fn from(x: Align) -> Self {
+        x.0
+    }
+}
+
+impl TryFrom<Value> for Align {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let string = value.into_string()?;
+        let align = match string.str() {
+            "left" => ggez::graphics::Align::Left,
+            "center" => ggez::graphics::Align::Center,
+            "right" => ggez::graphics::Align::Right,
+            _ => {
+                return Err(rterr!(
+                    "Expected 'left', 'center' or 'right', but got {:?}",
+                    string
+                ))
+            }
+        };
+        Ok(Self(align))
+    }
+}
@@ -0,0 +1,92 @@
+use super::*;
+use crate::Handle;
+use crate::RcStr;
+
+/// Native types that can be passed to draw.
+///
+/// To add a new drawable type, implement this trait and
+/// add an entry for it in DRAWABLES
+pub trait AsDrawable: 'static {
+    fn as_drawable(&mut self) -> &mut dyn Drawable;
+}
+
+impl AsDrawable for Text {
+    fn as_drawable(&mut self) -> &mut dyn Drawable {
+        self
+    }
+}
+
+impl AsDrawable for Mesh {
+    fn as_drawable(&mut self) -> &mut dyn Drawable {
+        self.get_mut()
+    }
+}
+
+impl AsDrawable for Image {
+    fn as_drawable(&mut self) -> &mut dyn Drawable {
+        self.get_mut()
+    }
+}
+
+impl AsDrawable for SpriteBatch {
+    fn as_drawable(&mut self) -> &mut dyn Drawable {
+        self.get_mut()
+    }
+}
+
+impl AsDrawable for Canvas {
+    fn as_drawable(&mut self) -> &mut dyn Drawable {
+        self.get_mut()
+    }
+}
+
+type DrawFn = fn(&mut Globals, &Value, DrawParam) -> Result<bool>;
+
+const DRAWABLES: &[DrawFn] = &[
+    draw_as::<Text>,
+    draw_as::<Mesh>,
+    draw_as::<Image>,
+    draw_as::<SpriteBatch>,
+    draw_as::<Canvas>,
+];
+
+/// Draws the value if it is a T, and returns whether it was
+fn draw_as<T: AsDrawable>(globals: &mut Globals, value: &Value, param: DrawParam) -> Result<bool> {
+    if !value.is_handle::<T>() {
+        return Ok(false);
+    }
+    let handle: Handle<T> = value.clone().into_handle::<T>()?;
+    let blend_mode = blend_mode(globals)?;
+    let ctx = match drawctx(globals)? {
+        Some(ctx) => ctx,
+        None => return Ok(true),
+    };
+    draw_with_blend_mode(ctx, handle.borrow_mut().as_drawable(), param, blend_mode)?;
+    Ok(true)
+}
+
+/// Draws any drawable value.
+///
+/// Values that are not native drawables are drawn by calling their
+/// 'draw' method with the DrawParam, so that mtots objects can draw
+/// themselves. Anything else (including classes, whose 'draw' would
+/// be unbound) is an error
+pub fn draw_value(globals: &mut Globals, value: Value, param: DrawParam) -> Result<()> {
+    for draw in DRAWABLES {
+        if draw(globals, &value, param)? {
+            return Ok(());
+        }
+    }
+    let method = match &value {
+        Value::Class(_) => None,
+        value => value.getattr_opt(&RcStr::from("draw")),
+    };
+    match method {
+        Some(method) => {
+            let param = globals.new_handle(DrawParamW::from(param))?;
+            method.apply(globals, vec![param.into()], None)?;
+            Ok(())
+        }
+        None => Err(rterr!("Expected a drawable, but got {:?}", value)),
+    }
+}
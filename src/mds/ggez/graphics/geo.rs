@@ -15,9 +15,51 @@ impl From<ggez::graphics::DrawMode> for DrawMode {
     }
 }
 
+/// A draw mode is either 'fill', 'stroke', or a map of stroke options,
+/// e.g. ['width': 4, 'join': 'round', 'cap': 'round']
 impl TryFrom<Value> for DrawMode {
     type Error = Error;
     fn try_from(value: Value) -> Result<Self> {
+        if let Value::Map(map) = &value {
+            let mut map = map.to_string_keys()?;
+            let mut options = ggez::graphics::StrokeOptions::default().with_line_width(2.0);
+            if let Some(widthval) = map.remove("width") {
+                options = options.with_line_width(widthval.f32()?);
+            }
+            if let Some(joinval) = map.remove("join") {
+                let join = joinval.into_string()?;
+                options = options.with_line_join(match join.str() {
+                    "miter" => ggez::graphics::LineJoin::Miter,
+                    "round" => ggez::graphics::LineJoin::Round,
+                    "bevel" => ggez::graphics::LineJoin::Bevel,
+                    _ => {
+                        return Err(rterr!(
+                            "Expected 'miter', 'round' or 'bevel', but got {:?}",
+                            join
+                        ))
+                    }
+                });
+            }
+            if let Some(capval) = map.remove("cap") {
+                let cap = capval.into_string()?;
+                options = options.with_line_cap(match cap.str() {
+                    "butt" => ggez::graphics::LineCap::Butt,
+                    "square" => ggez::graphics::LineCap::Square,
+                    "round" => ggez::graphics::LineCap::Round,
+                    _ => {
+                        return Err(rterr!(
+                            "Expected 'butt', 'square' or 'round', but got {:?}",
+                            cap
+                        ))
+                    }
+                });
+            }
+            if !map.is_empty() {
+                let keys: Vec<_> = map.keys().collect();
+                return Err(rterr!("Unused stroke attributes: {:?}", keys));
+            }
+            return Ok(ggez::graphics::DrawMode::Stroke(options).into());
+        }
         let string = value.into_string()?;
         let dm = match string.str() {
             "fill" => ggez::graphics::DrawMode::fill().into(),
@@ -43,6 +85,19 @@ impl From<ggez::graphics::Rect> for Rect {
     }
 }
 
+/// Converts into the [x, y, w, h] form
+impl From<Rect> for Value {
+    fn from(x: Rect) -> Self {
+        vec![
+            Value::from(x.0.x as f64),
+            Value::from(x.0.y as f64),
+            Value::from(x.0.w as f64),
+            Value::from(x.0.h as f64),
+        ]
+        .into()
+    }
+}
+
 impl ConvertValue for Rect {
     fn convert(_globals: &mut Globals, value: &Value) -> Result<Self> {
         match value {
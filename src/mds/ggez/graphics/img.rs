@@ -1,6 +1,10 @@
 use crate::mtry;
+use crate::rterr;
 use crate::ConvertValue;
+use crate::Error;
 use crate::Result;
+use crate::Value;
+use std::convert::TryFrom;
 use std::path::Path;
 
 #[derive(Clone)]
@@ -22,6 +26,9 @@ impl Image {
     pub fn get(&self) -> &ggez::graphics::Image {
         &self.0
     }
+    pub fn get_mut(&mut self) -> &mut ggez::graphics::Image {
+        &mut self.0
+    }
     pub fn from_file<P: AsRef<Path>>(ctx: &mut ggez::Context, p: P) -> Result<Image> {
         let bytes = std::fs::read(p)?;
         Self::from_bytes(ctx, &bytes)
@@ -37,6 +44,189 @@ impl Image {
         ));
         Ok(Self(im))
     }
+    pub fn from_rgba8(
+        ctx: &mut ggez::Context,
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+    ) -> Result<Image> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(rterr!(
+                "Expected {} bytes for a {}x{} RGBA image, but got {}",
+                expected,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+        Ok(Self(mtry!(ggez::graphics::Image::from_rgba8(
+            ctx, width, height, pixels
+        ))))
+    }
+    pub fn solid(
+        ctx: &mut ggez::Context,
+        size: u16,
+        color: ggez::graphics::Color,
+    ) -> Result<Image> {
+        Ok(Self(mtry!(ggez::graphics::Image::solid(ctx, size, color))))
+    }
+    /// Copies out the given region (in pixels) of this image into a new image
+    pub fn sub_image(
+        &self,
+        ctx: &mut ggez::Context,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    ) -> Result<Image> {
+        if width == 0 || height == 0 {
+            return Err(rterr!(
+                "Region [{}, {}, {}, {}] is empty",
+                x,
+                y,
+                width,
+                height
+            ));
+        }
+        let (imw, imh) = (self.0.width(), self.0.height());
+        if x as usize + width as usize > imw as usize || y as usize + height as usize > imh as usize
+        {
+            return Err(rterr!(
+                "Region [{}, {}, {}, {}] is out of bounds for a {}x{} image",
+                x,
+                y,
+                width,
+                height,
+                imw,
+                imh
+            ));
+        }
+        let pixels = self.to_rgba8(ctx)?;
+        let mut out = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in y as usize..y as usize + height as usize {
+            let start = (row * imw as usize + x as usize) * 4;
+            out.extend_from_slice(&pixels[start..start + width as usize * 4]);
+        }
+        let mut im = Self::from_rgba8(ctx, width, height, &out)?;
+        im.0.set_filter(self.0.filter());
+        Ok(im)
+    }
+    pub fn to_rgba8(&self, ctx: &mut ggez::Context) -> Result<Vec<u8>> {
+        Ok(mtry!(self.0.to_rgba8(ctx)))
+    }
+    /// Encodes the image into the given format ('png', 'jpeg' or 'bmp')
+    pub fn encode(&self, ctx: &mut ggez::Context, format: &str) -> Result<Vec<u8>> {
+        let format = match format {
+            "png" => image::ImageOutputFormat::Png,
+            "jpeg" | "jpg" => image::ImageOutputFormat::Jpeg(90),
+            "bmp" => image::ImageOutputFormat::Bmp,
+            _ => {
+                return Err(rterr!(
+                    "Expected 'png', 'jpeg' or 'bmp', but got {:?}",
+                    format
+                ))
+            }
+        };
+        let width = self.0.width() as u32;
+        let height = self.0.height() as u32;
+        let pixels = self.to_rgba8(ctx)?;
+        let im = match image::RgbaImage::from_raw(width, height, pixels) {
+            Some(im) => im,
+            None => return Err(rterr!("Image pixel data does not match its dimensions")),
+        };
+        let mut bytes = Vec::new();
+        mtry!(image::DynamicImage::ImageRgba8(im).write_to(&mut bytes, format));
+        Ok(bytes)
+    }
 }
 
 impl ConvertValue for Image {}
+
+#[derive(Clone, Copy)]
+pub struct FilterMode(ggez::graphics::FilterMode);
+
+impl From<FilterMode> for ggez::graphics::FilterMode {
+    fn from(x: FilterMode) -> Self {
+        x.0
+    }
+}
+
+impl From<ggez::graphics::FilterMode> for FilterMode {
+    fn from(x: ggez::graphics::FilterMode) -> Self {
+        Self(x)
+    }
+}
+
+impl From<FilterMode> for Value {
+    fn from(x: FilterMode) -> Self {
+        match x.0 {
+            ggez::graphics::FilterMode::Nearest => "nearest".into(),
+            ggez::graphics::FilterMode::Linear => "linear".into(),
+        }
+    }
+}
+
+impl TryFrom<Value> for FilterMode {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let string = value.into_string()?;
+        let fm = match string.str() {
+            "nearest" => ggez::graphics::FilterMode::Nearest,
+            "linear" => ggez::graphics::FilterMode::Linear,
+            _ => {
+                return Err(rterr!(
+                    "Expected 'nearest' or 'linear', but got {:?}",
+                    string
+                ))
+            }
+        };
+        Ok(fm.into())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct WrapMode(ggez::graphics::WrapMode);
+
+impl From<WrapMode> for ggez::graphics::WrapMode {
+    fn from(x: WrapMode) -> Self {
+        x.0
+    }
+}
+
+impl From<ggez::graphics::WrapMode> for WrapMode {
+    fn from(x: ggez::graphics::WrapMode) -> Self {
+        Self(x)
+    }
+}
+
+impl From<WrapMode> for Value {
+    fn from(x: WrapMode) -> Self {
+        match x.0 {
+            ggez::graphics::WrapMode::Tile => "tile".into(),
+            ggez::graphics::WrapMode::Mirror => "mirror".into(),
+            ggez::graphics::WrapMode::Clamp => "clamp".into(),
+            ggez::graphics::WrapMode::Border => "border".into(),
+        }
+    }
+}
+
+impl TryFrom<Value> for WrapMode {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let string = value.into_string()?;
+        let wm = match string.str() {
+            "tile" => ggez::graphics::WrapMode::Tile,
+            "mirror" => ggez::graphics::WrapMode::Mirror,
+            "clamp" => ggez::graphics::WrapMode::Clamp,
+            "border" => ggez::graphics::WrapMode::Border,
+            _ => {
+                return Err(rterr!(
+                    "Expected 'tile', 'mirror', 'clamp' or 'border', but got {:?}",
+                    string
+                ))
+            }
+        };
+        Ok(wm.into())
+    }
+}
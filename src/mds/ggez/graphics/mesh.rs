@@ -8,6 +8,19 @@ impl Mesh {
     pub fn get(&self) -> &ggez::graphics::Mesh {
         &self.0
     }
+    pub fn get_mut(&mut self) -> &mut ggez::graphics::Mesh {
+        &mut self.0
+    }
+    pub fn from_raw(
+        ctx: &mut ggez::Context,
+        vertices: &[ggez::graphics::Vertex],
+        indices: &[u32],
+        image: Option<ggez::graphics::Image>,
+    ) -> Result<Mesh> {
+        Ok(Mesh(mtry!(ggez::graphics::Mesh::from_raw(
+            ctx, vertices, indices, image
+        ))))
+    }
 }
 
 pub struct MeshBuilder(Option<ggez::graphics::MeshBuilder>);
@@ -41,3 +54,165 @@ impl TryFrom<MeshBuilder> for ggez::graphics::MeshBuilder {
         mb.transfer()
     }
 }
+
+#[derive(Clone, Copy)]
+pub struct Vertex(ggez::graphics::Vertex);
+
+impl From<Vertex> for ggez::graphics::Vertex {
+    fn from(x: Vertex) -> Self {
+        x.0
+    }
+}
+
+/// A vertex is given either as [x, y, u, v], [x, y, u, v, color],
+/// or as a map with 'pos', 'uv' and 'color' keys
+impl TryFrom<Value> for Vertex {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let mut pos = [0.0, 0.0];
+        let mut uv = [0.0, 0.0];
+        let mut color: ggez::graphics::Color = ggez::graphics::WHITE;
+        match &value {
+            Value::List(list) => {
+                let list: Vec<Value> = list.borrow().iter().cloned().collect();
+                if list.len() != 4 && list.len() != 5 {
+                    return Err(rterr!(
+                        "Expected vertex [x, y, u, v] or [x, y, u, v, color], but got {:?}",
+                        value
+                    ));
+                }
+                let mut iter = list.into_iter();
+                pos[0] = f32::try_from(iter.next().unwrap())?;
+                pos[1] = f32::try_from(iter.next().unwrap())?;
+                uv[0] = f32::try_from(iter.next().unwrap())?;
+                uv[1] = f32::try_from(iter.next().unwrap())?;
+                if let Some(colorval) = iter.next() {
+                    color = Color::try_from(colorval)?.into();
+                }
+            }
+            Value::Map(map) => {
+                let mut map = map.to_string_keys()?;
+                if let Some(posval) = map.remove("pos") {
+                    pos = <[f32; 2]>::try_from(posval)?;
+                }
+                if let Some(uvval) = map.remove("uv") {
+                    uv = <[f32; 2]>::try_from(uvval)?;
+                }
+                if let Some(colorval) = map.remove("color") {
+                    color = Color::try_from(colorval)?.into();
+                }
+                if !map.is_empty() {
+                    let keys: Vec<_> = map.keys().collect();
+                    return Err(rterr!("Unused vertex attributes: {:?}", keys));
+                }
+            }
+            _ => return Err(rterr!("Expected vertex, but got {:?}", value)),
+        }
+        Ok(Vertex(ggez::graphics::Vertex {
+            pos,
+            uv,
+            color: [color.r, color.g, color.b, color.a],
+        }))
+    }
+}
+
+pub fn vertices_from_value(value: Value) -> Result<Vec<ggez::graphics::Vertex>> {
+    let list = value.into_list()?;
+    let list: Vec<Value> = list.borrow().iter().cloned().collect();
+    list.into_iter()
+        .map(|v| Ok(Vertex::try_from(v)?.into()))
+        .collect()
+}
+
+/// Triangle indices into a list of nvertices vertices
+pub fn indices_from_value(value: Value, nvertices: usize) -> Result<Vec<u32>> {
+    let indices = Vec::<usize>::try_from(value)?;
+    if indices.len() % 3 != 0 {
+        return Err(rterr!(
+            "The number of indices must be a multiple of 3, but got {}",
+            indices.len()
+        ));
+    }
+    if let Some(i) = indices.iter().find(|i| **i >= nvertices) {
+        return Err(rterr!(
+            "Index {} is out of bounds for {} vertices",
+            i,
+            nvertices
+        ));
+    }
+    Ok(indices.into_iter().map(|i| i as u32).collect())
+}
+
+/// Points along the arc of a circle, going clockwise (on screen)
+/// from start to end (angles in radians)
+pub fn arc_points(
+    [cx, cy]: [f32; 2],
+    radius: f32,
+    start: f32,
+    end: f32,
+    segments: usize,
+) -> Vec<[f32; 2]> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let angle = start + (end - start) * (i as f32) / (segments as f32);
+            [cx + radius * angle.cos(), cy + radius * angle.sin()]
+        })
+        .collect()
+}
+
+pub fn quadratic_bezier_points([p0, p1, p2]: [[f32; 2]; 3], segments: usize) -> Vec<[f32; 2]> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let t = (i as f32) / (segments as f32);
+            let u = 1.0 - t;
+            let a = u * u;
+            let b = 2.0 * u * t;
+            let c = t * t;
+            [
+                a * p0[0] + b * p1[0] + c * p2[0],
+                a * p0[1] + b * p1[1] + c * p2[1],
+            ]
+        })
+        .collect()
+}
+
+pub fn cubic_bezier_points([p0, p1, p2, p3]: [[f32; 2]; 4], segments: usize) -> Vec<[f32; 2]> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let t = (i as f32) / (segments as f32);
+            let u = 1.0 - t;
+            let a = u * u * u;
+            let b = 3.0 * u * u * t;
+            let c = 3.0 * u * t * t;
+            let d = t * t * t;
+            [
+                a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+                a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+            ]
+        })
+        .collect()
+}
+
+/// The outline of a rectangle with rounded corners, in clockwise order (on screen)
+pub fn rounded_rectangle_points(
+    rect: ggez::graphics::Rect,
+    radius: f32,
+    segments: usize,
+) -> Vec<[f32; 2]> {
+    use std::f32::consts::PI;
+    let radius = radius.min(rect.w / 2.0).min(rect.h / 2.0).max(0.0);
+    let left = rect.x + radius;
+    let right = rect.x + rect.w - radius;
+    let top = rect.y + radius;
+    let bottom = rect.y + rect.h - radius;
+    let mut points = Vec::new();
+    points.extend(arc_points([right, top], radius, -PI / 2.0, 0.0, segments));
+    points.extend(arc_points([right, bottom], radius, 0.0, PI / 2.0, segments));
+    points.extend(arc_points([left, bottom], radius, PI / 2.0, PI, segments));
+    points.extend(arc_points([left, top], radius, PI, PI * 1.5, segments));
+    points.dedup();
+    points
+}
@@ -1,34 +1,60 @@
 use super::*;
+use crate::Key;
+use crate::Map;
 use ggez::graphics::DrawParam;
 use ggez::graphics::Drawable;
 
+mod anim;
+mod atlas;
+mod blend;
+mod canvas;
 mod conv;
+mod drawable;
 mod geo;
 mod img;
 mod mesh;
+mod param;
 mod sprite;
+mod text;
+mod tiled;
+mod tilemap;
+mod transform;
+pub use anim::*;
+pub use atlas::*;
+pub use blend::*;
+pub use canvas::*;
 pub use conv::*;
+pub use drawable::*;
 pub use geo::*;
 pub use img::*;
 pub use mesh::*;
+pub use param::*;
 pub use sprite::*;
+pub use text::*;
+pub use tiled::*;
+pub use tilemap::*;
+pub use transform::*;
 
 pub const NAME: &str = "a.ggez.graphics";
 
 pub(in super::super) fn new() -> NativeModule {
     NativeModule::new(NAME, |m| {
-        m.func("width", [], "", |globals, _, _| {
-            let ctx = getctx(globals)?;
-            Ok(ggez::graphics::drawable_size(ctx).0.into())
+        m.dep("a.bytes", None, &[]);
+        m.func("width", [], "", |globals, _, _| match drawctx(globals)? {
+            Some(ctx) => Ok(ggez::graphics::drawable_size(ctx).0.into()),
+            None => Ok(headless_size(globals)[0].into()),
         });
-        m.func("height", [], "", |globals, _, _| {
-            let ctx = getctx(globals)?;
-            Ok(ggez::graphics::drawable_size(ctx).1.into())
+        m.func("height", [], "", |globals, _, _| match drawctx(globals)? {
+            Some(ctx) => Ok(ggez::graphics::drawable_size(ctx).1.into()),
+            None => Ok(headless_size(globals)[1].into()),
         });
         m.func("clear", ["color"], "", |globals, args, _| {
             let mut args = args.into_iter();
             let color = Color::try_from(args.next().unwrap())?;
-            let ctx = getctx(globals)?;
+            let ctx = match drawctx(globals)? {
+                Some(ctx) => ctx,
+                None => return Ok(Value::Nil),
+            };
             ggez::graphics::clear(ctx, color.into());
             Ok(Value::Nil)
         });
@@ -43,79 +69,52 @@ pub(in super::super) fn new() -> NativeModule {
                 .def("yscale", 1)
                 .def("xoffset", 0)
                 .def("yoffset", 0)
-                .def("color", ()),
-            "",
+                .def("color", ())
+                .def("src", [0, 0, 1, 1])
+                .def("param", ()),
+            concat!(
+                "Draws a Text, Mesh, Image, SpriteBatch or Canvas. ",
+                "Any other object has its draw method called with the DrawParam, ",
+                "so that mtots objects can draw themselves.\n",
+                "If param (a DrawParam) is given, the other drawing arguments are ignored",
+            ),
             |globals, args, _| {
                 let mut args = args.into_iter();
                 let drawable = args.next().unwrap();
-                let x = f32::try_from(args.next().unwrap())?;
-                let y = f32::try_from(args.next().unwrap())?;
-                let rotation = f32::try_from(args.next().unwrap())?;
-                let xscale = f32::try_from(args.next().unwrap())?;
-                let yscale = f32::try_from(args.next().unwrap())?;
-                let xoffset = f32::try_from(args.next().unwrap())?;
-                let yoffset = f32::try_from(args.next().unwrap())?;
-                let colorval = args.next().unwrap();
-
-                let drawparam = DrawParam::default()
-                    .dest([x, y])
-                    .rotation(rotation)
-                    .scale([xscale, yscale])
-                    .offset([xoffset, yoffset])
-                    .color(if colorval.is_nil() {
-                        ggez::graphics::WHITE
-                    } else {
-                        Color::try_from(colorval)?.into()
-                    });
-
-                let ctx = getctx(globals)?;
-                if drawable.is_handle::<Text>() {
-                    mtry!(ggez::graphics::draw(
-                        ctx,
-                        drawable.to_xref::<Text>(globals)?.get(),
-                        drawparam,
-                    ));
-                } else if drawable.is_handle::<Mesh>() {
-                    mtry!(ggez::graphics::draw(
-                        ctx,
-                        drawable.to_xref::<Mesh>(globals)?.get(),
-                        drawparam,
-                    ));
-                } else if drawable.is_handle::<Image>() {
-                    mtry!(ggez::graphics::draw(
-                        ctx,
-                        drawable.to_xref::<Image>(globals)?.get(),
-                        drawparam,
-                    ));
-                } else if drawable.is_handle::<SpriteBatch>() {
-                    mtry!(ggez::graphics::draw(
-                        ctx,
-                        drawable.to_xref::<SpriteBatch>(globals)?.get(),
-                        drawparam,
-                    ));
-                } else {
-                    return Err(rterr!("Expected drawable but got {:?}", drawable));
-                }
-
+                let drawparam = drawparam_from_args(globals, &mut args)?;
+                let drawparam = match args.next().unwrap() {
+                    Value::Nil => drawparam,
+                    value => value.convert::<DrawParamW>(globals)?.into(),
+                };
+                draw_value(globals, drawable, drawparam)?;
                 Ok(Value::Nil)
             },
         );
         m.func(
             "print",
-            ArgSpec::builder().req("text").def("x", 0).def("y", 0),
-            "",
+            ArgSpec::builder()
+                .req("text")
+                .def("x", 0)
+                .def("y", 0)
+                .def("rotation", 0)
+                .def("xscale", 1)
+                .def("yscale", 1)
+                .def("xoffset", 0)
+                .def("yoffset", 0)
+                .def("color", ())
+                .def("src", [0, 0, 1, 1]),
+            "Like draw, but the text argument may be anything that can be converted to a Text",
             |globals, args, _| {
                 let mut args = args.into_iter();
                 let textval = args.next().unwrap();
-                let text = textval.to_xref::<Text>(globals)?;
-                let x = f32::try_from(args.next().unwrap())?;
-                let y = f32::try_from(args.next().unwrap())?;
-                let ctx = getctx(globals)?;
-                mtry!(ggez::graphics::draw(
-                    ctx,
-                    text.get(),
-                    DrawParam::default().dest([x, y])
-                ));
+                let mut text = textval.convert::<Text>(globals)?;
+                let drawparam = drawparam_from_args(globals, &mut args)?;
+                let blend_mode = blend_mode(globals)?;
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                draw_with_blend_mode(ctx, &mut text, drawparam, blend_mode)?;
                 Ok(Value::Nil)
             },
         );
@@ -137,37 +136,467 @@ pub(in super::super) fn new() -> NativeModule {
                     Value::Nil => None,
                     value => Some(Color::try_from(value)?.into()),
                 };
-                let ctx = getctx(globals)?;
-                ggez::graphics::queue_text(ctx, text.get(), [x, y], color);
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                text.queue(ctx, [x, y], color);
                 Ok(Value::Nil)
             },
         );
         m.func(
             "draw_queued_text",
-            ArgSpec::builder().def("x", 0).def("y", 0),
-            "",
+            ArgSpec::builder()
+                .def("x", 0)
+                .def("y", 0)
+                .def("rotation", 0)
+                .def("xscale", 1)
+                .def("yscale", 1)
+                .def("xoffset", 0)
+                .def("yoffset", 0)
+                .def("color", ())
+                .def("src", [0, 0, 1, 1])
+                .def("filter", ()),
+            concat!(
+                "Draws all text queued with queue_text. ",
+                "If filter is not given, the default filter mode is used",
+            ),
             |globals, args, _| {
                 let mut args = args.into_iter();
-                let x = f32::try_from(args.next().unwrap())?;
-                let y = f32::try_from(args.next().unwrap())?;
-                let ctx = getctx(globals)?;
+                let drawparam = drawparam_from_args(globals, &mut args)?;
+                let filter = match args.next().unwrap() {
+                    Value::Nil => default_filter(globals)?,
+                    value => FilterMode::try_from(value)?.into(),
+                };
+                let blend_mode = blend_mode(globals)?;
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
                 mtry!(ggez::graphics::draw_queued_text(
-                    ctx,
-                    DrawParam::default().dest([x, y]),
-                    None,
-                    ggez::graphics::FilterMode::Linear,
+                    ctx, drawparam, blend_mode, filter,
                 ));
                 Ok(Value::Nil)
             },
         );
+        m.func(
+            "screenshot",
+            [],
+            concat!(
+                "Returns the current contents of the screen as an Image, ",
+                "or nil when running headless without rendering",
+            ),
+            |globals, _, _| {
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                let im = Image::from(mtry!(ggez::graphics::screenshot(ctx)));
+                Ok(globals.new_handle(im)?.into())
+            },
+        );
+        m.func(
+            "set_blend_mode",
+            ["mode"],
+            concat!(
+                "Sets the blend mode used by subsequent draws ('alpha', 'add', ",
+                "'subtract', 'multiply', 'replace', 'lighten', 'darken' or 'invert'). ",
+                "nil restores each drawable's own blend mode",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let mode = match args.next().unwrap() {
+                    Value::Nil => None,
+                    value => Some(BlendMode::try_from(value)?.into()),
+                };
+                set_blend_mode(globals, mode)?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "set_default_filter",
+            ["mode"],
+            concat!(
+                "Sets the filter mode ('nearest' or 'linear') used for images ",
+                "created from now on, and for draw_queued_text",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let mode = FilterMode::try_from(args.next().unwrap())?;
+                set_default_filter(globals, mode.into())?;
+                Ok(Value::Nil)
+            },
+        );
         m.func("set_window_title", ["title"], "", |globals, args, _| {
             let mut args = args.into_iter();
             let title = args.next().unwrap().into_string()?;
-            let ctx = getctx(globals)?;
+            let ctx = match drawctx(globals)? {
+                Some(ctx) => ctx,
+                None => return Ok(Value::Nil),
+            };
             ggez::graphics::set_window_title(ctx, title.str());
             Ok(Value::Nil)
         });
+        m.func(
+            "set_mode",
+            ["mode"],
+            concat!(
+                "Sets the window mode. Options not given in the map ",
+                "are reset to their defaults",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let mode = WindowMode::try_from(args.next().unwrap())?;
+                let ctx = getctx(globals)?;
+                mtry!(ggez::graphics::set_mode(ctx, mode.into()));
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "set_fullscreen",
+            ["fullscreen"],
+            "fullscreen may be 'windowed', 'true', 'desktop', or a bool",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let fullscreen = match args.next().unwrap() {
+                    Value::Bool(true) => ggez::conf::FullscreenType::True,
+                    Value::Bool(false) => ggez::conf::FullscreenType::Windowed,
+                    value => FullscreenType::try_from(value)?.into(),
+                };
+                let ctx = getctx(globals)?;
+                mtry!(ggez::graphics::set_fullscreen(ctx, fullscreen));
+                Ok(Value::Nil)
+            },
+        );
+        m.func("set_resizable", ["resizable"], "", |globals, args, _| {
+            let mut args = args.into_iter();
+            let resizable = args.next().unwrap().truthy();
+            let ctx = getctx(globals)?;
+            mtry!(ggez::graphics::set_resizable(ctx, resizable));
+            Ok(Value::Nil)
+        });
+        m.func("set_size", ["width", "height"], "", |globals, args, _| {
+            let mut args = args.into_iter();
+            let width = args.next().unwrap().f32()?;
+            let height = args.next().unwrap().f32()?;
+            let ctx = getctx(globals)?;
+            mtry!(ggez::graphics::set_drawable_size(ctx, width, height));
+            Ok(Value::Nil)
+        });
+        m.func(
+            "set_window_icon",
+            ["path"],
+            "Sets the window icon from a resource path, or clears it if nil",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let path = match args.next().unwrap() {
+                    Value::Nil => None,
+                    value => Some(value.into_string()?),
+                };
+                let ctx = getctx(globals)?;
+                mtry!(ggez::graphics::set_window_icon(
+                    ctx,
+                    path.as_ref().map(|path| path.str())
+                ));
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "push",
+            [],
+            "Pushes a copy of the current transform onto the transform stack",
+            |globals, _, _| {
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                push_transform(ctx)?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "pop",
+            [],
+            "Restores the transform saved by the matching call to push",
+            |globals, _, _| {
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                pop_transform(ctx)?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "with_transform",
+            ["f"],
+            "Calls f between a push and a pop of the transform stack",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let f = args.next().unwrap();
+                if drawctx(globals)?.is_none() {
+                    return f.apply(globals, vec![], None);
+                }
+                push_transform(getctx(globals)?)?;
+                let r = f.apply(globals, vec![], None);
+                pop_transform(getctx(globals)?)?;
+                r
+            },
+        );
+        m.func("translate", ["x", "y"], "", |globals, args, _| {
+            let mut args = args.into_iter();
+            let x = f32::try_from(args.next().unwrap())?;
+            let y = f32::try_from(args.next().unwrap())?;
+            let ctx = match drawctx(globals)? {
+                Some(ctx) => ctx,
+                None => return Ok(Value::Nil),
+            };
+            concat_transform(ctx, translation(x, y))?;
+            Ok(Value::Nil)
+        });
+        m.func(
+            "rotate",
+            ["angle"],
+            "Rotates by the given angle (in radians) around the current origin",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let angle = f32::try_from(args.next().unwrap())?;
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                concat_transform(ctx, rotation(angle))?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "scale",
+            ArgSpec::builder().req("x").def("y", ()),
+            "If y is not given, x is used for both axes",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let x = f32::try_from(args.next().unwrap())?;
+                let y = match args.next().unwrap() {
+                    Value::Nil => x,
+                    value => f32::try_from(value)?,
+                };
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                concat_transform(ctx, scaling(x, y))?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "set_transform",
+            ArgSpec::builder()
+                .def("x", 0)
+                .def("y", 0)
+                .def("rotation", 0)
+                .def("xscale", 1)
+                .def("yscale", 1)
+                .def("xoffset", 0)
+                .def("yoffset", 0),
+            concat!(
+                "Replaces the current transform, using the same parameters as draw. ",
+                "With no arguments, resets the current transform to the identity",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let x = f32::try_from(args.next().unwrap())?;
+                let y = f32::try_from(args.next().unwrap())?;
+                let rotation = f32::try_from(args.next().unwrap())?;
+                let xscale = f32::try_from(args.next().unwrap())?;
+                let yscale = f32::try_from(args.next().unwrap())?;
+                let xoffset = f32::try_from(args.next().unwrap())?;
+                let yoffset = f32::try_from(args.next().unwrap())?;
+                let matrix = DrawParam::default()
+                    .dest([x, y])
+                    .rotation(rotation)
+                    .scale([xscale, yscale])
+                    .offset([xoffset, yoffset])
+                    .to_matrix();
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                set_transform(ctx, matrix.into())?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "set_screen_coordinates",
+            ["rect"],
+            concat!(
+                "Sets the region of the world that is visible on the screen. ",
+                "Useful for scrolling cameras and zooming",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let rect = args.next().unwrap().convert::<Rect>(globals)?;
+                let ctx = match drawctx(globals)? {
+                    Some(ctx) => ctx,
+                    None => return Ok(Value::Nil),
+                };
+                mtry!(ggez::graphics::set_screen_coordinates(ctx, rect.into()));
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "screen_coordinates",
+            [],
+            "Returns the visible region of the world as [x, y, w, h]",
+            |globals, _, _| {
+                let rect = match drawctx(globals)? {
+                    Some(ctx) => ggez::graphics::screen_coordinates(ctx),
+                    None => {
+                        let [width, height] = headless_size(globals);
+                        ggez::graphics::Rect::new(0.0, 0.0, width, height)
+                    }
+                };
+                Ok(Rect::from(rect).into())
+            },
+        );
+        m.func(
+            "set_canvas",
+            ["canvas"],
+            concat!(
+                "Makes subsequent drawing calls render to the given Canvas, ",
+                "or to the screen if canvas is nil",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let canvas = match args.next().unwrap() {
+                    Value::Nil => None,
+                    value => Some(value.into_handle::<Canvas>()?),
+                };
+                set_canvas(globals, canvas)?;
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "with_canvas",
+            ["canvas", "f"],
+            concat!(
+                "Calls f with all drawing calls rendering to the given Canvas, ",
+                "then restores the previous render target",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let canvas = args.next().unwrap().into_handle::<Canvas>()?;
+                let f = args.next().unwrap();
+                let previous = current_canvas(globals);
+                set_canvas(globals, Some(canvas))?;
+                let r = f.apply(globals, vec![], None);
+                set_canvas(globals, previous)?;
+                r
+            },
+        );
+        m.class::<DrawParamW, _>("DrawParam", |cls| {
+            cls.doc(concat!(
+                "A reusable set of drawing parameters, that can be passed to draw ",
+                "with the param argument",
+            ));
+            cls.sfunc(
+                "__call",
+                ArgSpec::builder()
+                    .def("x", 0)
+                    .def("y", 0)
+                    .def("rotation", 0)
+                    .def("xscale", 1)
+                    .def("yscale", 1)
+                    .def("xoffset", 0)
+                    .def("yoffset", 0)
+                    .def("color", ())
+                    .def("src", [0, 0, 1, 1]),
+                "",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let drawparam = drawparam_from_args(globals, &mut args)?;
+                    Ok(globals.new_handle(DrawParamW::from(drawparam))?.into())
+                },
+            );
+            cls.ifunc("dest", [], "", |owner, _globals, _, _| {
+                let dest = owner.borrow().get().dest;
+                Ok(vec![Value::from(dest.x as f64), Value::from(dest.y as f64)].into())
+            });
+            cls.ifunc("set_dest", ["x", "y"], "", |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let x = f32::try_from(args.next().unwrap())?;
+                let y = f32::try_from(args.next().unwrap())?;
+                owner.borrow_mut().get_mut().dest = [x, y].into();
+                Ok(owner.into())
+            });
+            cls.ifunc("rotation", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().get().rotation as f64).into())
+            });
+            cls.ifunc(
+                "set_rotation",
+                ["rotation"],
+                "",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let rotation = f32::try_from(args.next().unwrap())?;
+                    owner.borrow_mut().get_mut().rotation = rotation;
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc("scale", [], "", |owner, _globals, _, _| {
+                let scale = owner.borrow().get().scale;
+                Ok(vec![Value::from(scale.x as f64), Value::from(scale.y as f64)].into())
+            });
+            cls.ifunc(
+                "set_scale",
+                ArgSpec::builder().req("x").def("y", ()),
+                "If y is not given, x is used for both axes",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let x = f32::try_from(args.next().unwrap())?;
+                    let y = match args.next().unwrap() {
+                        Value::Nil => x,
+                        value => f32::try_from(value)?,
+                    };
+                    owner.borrow_mut().get_mut().scale = [x, y].into();
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc("offset", [], "", |owner, _globals, _, _| {
+                let offset = owner.borrow().get().offset;
+                Ok(vec![Value::from(offset.x as f64), Value::from(offset.y as f64)].into())
+            });
+            cls.ifunc("set_offset", ["x", "y"], "", |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let x = f32::try_from(args.next().unwrap())?;
+                let y = f32::try_from(args.next().unwrap())?;
+                owner.borrow_mut().get_mut().offset = [x, y].into();
+                Ok(owner.into())
+            });
+            cls.ifunc("color", [], "", |owner, _globals, _, _| {
+                let color = owner.borrow().get().color;
+                Ok(vec![
+                    Value::from(color.r as f64),
+                    Value::from(color.g as f64),
+                    Value::from(color.b as f64),
+                    Value::from(color.a as f64),
+                ]
+                .into())
+            });
+            cls.ifunc("set_color", ["color"], "", |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let color = Color::try_from(args.next().unwrap())?;
+                owner.borrow_mut().get_mut().color = color.into();
+                Ok(owner.into())
+            });
+            cls.ifunc("set_src", ["src"], "", |owner, globals, args, _| {
+                let mut args = args.into_iter();
+                let src = args.next().unwrap().convert::<Rect>(globals)?;
+                owner.borrow_mut().get_mut().src = src.into();
+                Ok(owner.into())
+            });
+        });
         m.class::<Text, _>("Text", |cls| {
+            cls.doc("A block of text, made of one or more fragments");
             cls.sfunc("__call", ["arg"], "", |globals, args, _| {
                 let mut args = args.into_iter();
                 let arg = args.next().unwrap();
@@ -176,23 +605,118 @@ pub(in super::super) fn new() -> NativeModule {
             });
             cls.ifunc("width", [], "", |owner, globals, _, _| {
                 let ctx = getctx(globals)?;
-                Ok(owner.borrow().get().width(ctx).into())
+                Ok((owner.borrow().width(ctx) as f64).into())
             });
             cls.ifunc("height", [], "", |owner, globals, _, _| {
                 let ctx = getctx(globals)?;
-                Ok(owner.borrow().get().height(ctx).into())
+                Ok((owner.borrow().height(ctx) as f64).into())
             });
             cls.ifunc("contents", [], "", |owner, _globals, _, _| {
                 Ok(owner.borrow().get().contents().into())
             });
-        });
-        m.class::<MeshBuilder, _>("MeshBuilder", |cls| {
-            cls.sfunc("__call", [], "", |globals, _, _| {
-                Ok(globals.new_handle(MeshBuilder::new())?.into())
-            });
-            cls.ifunc("build", [], "", |owner, globals, _, _| {
-                let ctx = getctx(globals)?;
-                let mesh = owner.borrow_mut().build(ctx)?;
+            cls.ifunc(
+                "set_bounds",
+                ArgSpec::builder()
+                    .req("width")
+                    .def("height", ())
+                    .def("align", "left"),
+                concat!(
+                    "Wraps the text to the given width, and aligns each line ",
+                    "('left', 'center' or 'right'). ",
+                    "If height is not given, the text may be arbitrarily tall",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let width = f32::try_from(args.next().unwrap())?;
+                    let height = match args.next().unwrap() {
+                        Value::Nil => std::f32::INFINITY,
+                        value => f32::try_from(value)?,
+                    };
+                    let align = Align::try_from(args.next().unwrap())?;
+                    owner.borrow_mut().set_bounds([width, height], align.into());
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "set_line_spacing",
+                ["spacing"],
+                concat!(
+                    "Sets the distance between the tops of consecutive lines, ",
+                    "as a multiple of the line height (1 by default)",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let spacing = args.next().unwrap().f32()?;
+                    owner.borrow_mut().set_line_spacing(spacing)?;
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc("line_spacing", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().line_spacing() as f64).into())
+            });
+            cls.ifunc(
+                "set_font",
+                ArgSpec::builder().req("font").def("scale", ()),
+                "Sets the font (and optionally the scale) of all fragments of this text",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let font = args.next().unwrap().convert::<Font>(globals)?;
+                    let scale = match args.next().unwrap() {
+                        Value::Nil => {
+                            ggez::graphics::Scale::uniform(ggez::graphics::DEFAULT_FONT_SCALE)
+                        }
+                        value => ggez::graphics::Scale::uniform(value.f32()?),
+                    };
+                    owner.borrow_mut().set_font(font.into(), scale);
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "glyph_positions",
+                [],
+                "Returns the [x, y] position of each glyph, relative to the text's origin",
+                |owner, globals, _, _| {
+                    let ctx = getctx(globals)?;
+                    let owner = owner.borrow();
+                    let positions = owner.get().glyph_positions(ctx);
+                    Ok(positions
+                        .iter()
+                        .map(|p| {
+                            Value::from(vec![Value::from(p.x as f64), Value::from(p.y as f64)])
+                        })
+                        .collect::<Vec<_>>()
+                        .into())
+                },
+            );
+        });
+        m.class::<Font, _>("Font", |cls| {
+            cls.sfunc("default", [], "", |globals, _, _| {
+                Ok(globals
+                    .new_handle(Font::from(ggez::graphics::Font::default()))?
+                    .into())
+            });
+            cls.sfunc("from_file", ["path"], "", |globals, args, _| {
+                let ctx = getctx(globals)?;
+                let mut args = args.into_iter();
+                let path = args.next().unwrap().into_string()?;
+                let font = Font::from_file(ctx, path.str())?;
+                Ok(globals.new_handle(font)?.into())
+            });
+            cls.sfunc("from_bytes", ["bytes"], "", |globals, args, _| {
+                let ctx = getctx(globals)?;
+                let mut args = args.into_iter();
+                let bytes = args.next().unwrap().convert::<Vec<u8>>(globals)?;
+                let font = Font::from_bytes(ctx, &bytes)?;
+                Ok(globals.new_handle(font)?.into())
+            });
+        });
+        m.class::<MeshBuilder, _>("MeshBuilder", |cls| {
+            cls.sfunc("__call", [], "", |globals, _, _| {
+                Ok(globals.new_handle(MeshBuilder::new())?.into())
+            });
+            cls.ifunc("build", [], "", |owner, globals, _, _| {
+                let ctx = getctx(globals)?;
+                let mesh = owner.borrow_mut().build(ctx)?;
                 Ok(globals.new_handle(mesh)?.into())
             });
             cls.ifunc(
@@ -357,8 +881,214 @@ pub(in super::super) fn new() -> NativeModule {
                     Ok(owner.into())
                 },
             );
+            cls.ifunc(
+                "polyline",
+                ArgSpec::builder()
+                    .req("points")
+                    .def("mode", "stroke")
+                    .def("color", ()),
+                concat!(
+                    "Like polygon, but in stroke mode the shape is not closed. ",
+                    "mode may also be a map of stroke options, e.g. ",
+                    "['width': 4, 'join': 'round', 'cap': 'round']",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let points = Vec::<[f32; 2]>::try_from(args.next().unwrap())?;
+                    let mode = DrawMode::try_from(args.next().unwrap())?;
+                    let color = match args.next().unwrap() {
+                        Value::Nil => ggez::graphics::Color::from((1.0, 1.0, 1.0)).into(),
+                        value => Color::try_from(value)?,
+                    };
+                    mtry!(owner.borrow_mut().get_mut()?.polyline(
+                        mode.into(),
+                        &points,
+                        color.into()
+                    ));
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "rounded_rectangle",
+                ArgSpec::builder()
+                    .req("bounds")
+                    .req("radius")
+                    .def("mode", "fill")
+                    .def("segments", 8)
+                    .def("color", ()),
+                "segments is the number of line segments used for each corner",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let bounds = args.next().unwrap().convert::<Rect>(globals)?;
+                    let radius = args.next().unwrap().f32()?;
+                    let mode = DrawMode::try_from(args.next().unwrap())?;
+                    let segments = args.next().unwrap().usize()?;
+                    let color = match args.next().unwrap() {
+                        Value::Nil => ggez::graphics::Color::from((1.0, 1.0, 1.0)).into(),
+                        value => Color::try_from(value)?,
+                    };
+                    let points = rounded_rectangle_points(bounds.into(), radius, segments);
+                    mtry!(owner.borrow_mut().get_mut()?.polygon(
+                        mode.into(),
+                        &points,
+                        color.into()
+                    ));
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "arc",
+                ArgSpec::builder()
+                    .req("radius")
+                    .req("start")
+                    .req("end")
+                    .def("mode", "stroke")
+                    .def("point", ())
+                    .def("segments", 32)
+                    .def("color", ()),
+                concat!(
+                    "An arc from angle start to end (in radians, clockwise on screen). ",
+                    "In fill mode, this draws a pie slice",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let radius = args.next().unwrap().f32()?;
+                    let start = args.next().unwrap().f32()?;
+                    let end = args.next().unwrap().f32()?;
+                    let mode = DrawMode::try_from(args.next().unwrap())?;
+                    let center = match args.next().unwrap() {
+                        Value::Nil => [0.0, 0.0],
+                        value => <[f32; 2]>::try_from(value)?,
+                    };
+                    let segments = args.next().unwrap().usize()?;
+                    let color = match args.next().unwrap() {
+                        Value::Nil => ggez::graphics::Color::from((1.0, 1.0, 1.0)).into(),
+                        value => Color::try_from(value)?,
+                    };
+                    let mut points = arc_points(center, radius, start, end, segments);
+                    let mode: ggez::graphics::DrawMode = mode.into();
+                    let mut mb = owner.borrow_mut();
+                    if let ggez::graphics::DrawMode::Fill(_) = mode {
+                        points.push(center);
+                        mtry!(mb.get_mut()?.polygon(mode, &points, color.into()));
+                    } else {
+                        mtry!(mb.get_mut()?.polyline(mode, &points, color.into()));
+                    }
+                    drop(mb);
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "quadratic_bezier",
+                ArgSpec::builder()
+                    .req("points")
+                    .def("mode", "stroke")
+                    .def("segments", 32)
+                    .def("color", ()),
+                "points is [start, control, end]",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let points = <[[f32; 2]; 3]>::try_from(args.next().unwrap())?;
+                    let mode = DrawMode::try_from(args.next().unwrap())?;
+                    let segments = args.next().unwrap().usize()?;
+                    let color = match args.next().unwrap() {
+                        Value::Nil => ggez::graphics::Color::from((1.0, 1.0, 1.0)).into(),
+                        value => Color::try_from(value)?,
+                    };
+                    let points = quadratic_bezier_points(points, segments);
+                    mtry!(owner.borrow_mut().get_mut()?.polyline(
+                        mode.into(),
+                        &points,
+                        color.into()
+                    ));
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "cubic_bezier",
+                ArgSpec::builder()
+                    .req("points")
+                    .def("mode", "stroke")
+                    .def("segments", 32)
+                    .def("color", ()),
+                "points is [start, control1, control2, end]",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let points = <[[f32; 2]; 4]>::try_from(args.next().unwrap())?;
+                    let mode = DrawMode::try_from(args.next().unwrap())?;
+                    let segments = args.next().unwrap().usize()?;
+                    let color = match args.next().unwrap() {
+                        Value::Nil => ggez::graphics::Color::from((1.0, 1.0, 1.0)).into(),
+                        value => Color::try_from(value)?,
+                    };
+                    let points = cubic_bezier_points(points, segments);
+                    mtry!(owner.borrow_mut().get_mut()?.polyline(
+                        mode.into(),
+                        &points,
+                        color.into()
+                    ));
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "texture",
+                ["image"],
+                "Sets the image used to texture the mesh being built",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let image = args.next().unwrap().convert::<Image>(globals)?;
+                    owner.borrow_mut().get_mut()?.texture(image.into());
+                    Ok(owner.into())
+                },
+            );
+            cls.ifunc(
+                "raw",
+                ArgSpec::builder()
+                    .req("vertices")
+                    .req("indices")
+                    .def("image", ()),
+                concat!(
+                    "Adds raw vertices, where each vertex is [x, y, u, v], ",
+                    "[x, y, u, v, color] or a map with 'pos', 'uv' and 'color' keys, ",
+                    "and indices is a list of vertex indices, 3 per triangle",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let vertices = vertices_from_value(args.next().unwrap())?;
+                    let indices = indices_from_value(args.next().unwrap(), vertices.len())?;
+                    let image: Option<ggez::graphics::Image> = match args.next().unwrap() {
+                        Value::Nil => None,
+                        value => Some(value.convert::<Image>(globals)?.into()),
+                    };
+                    owner
+                        .borrow_mut()
+                        .get_mut()?
+                        .raw(&vertices, &indices, image);
+                    Ok(owner.into())
+                },
+            );
         });
         m.class::<Mesh, _>("Mesh", |cls| {
+            cls.sfunc(
+                "from_raw",
+                ArgSpec::builder()
+                    .req("vertices")
+                    .req("indices")
+                    .def("image", ()),
+                "See MeshBuilder.raw",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let vertices = vertices_from_value(args.next().unwrap())?;
+                    let indices = indices_from_value(args.next().unwrap(), vertices.len())?;
+                    let image: Option<ggez::graphics::Image> = match args.next().unwrap() {
+                        Value::Nil => None,
+                        value => Some(value.convert::<Image>(globals)?.into()),
+                    };
+                    let ctx = getctx(globals)?;
+                    let mesh = Mesh::from_raw(ctx, &vertices, &indices, image)?;
+                    Ok(globals.new_handle(mesh)?.into())
+                },
+            );
             cls.ifunc("width", [], "", |owner, globals, _, _| {
                 let ctx = getctx(globals)?;
                 match owner.borrow().get().dimensions(ctx) {
@@ -389,12 +1119,598 @@ pub(in super::super) fn new() -> NativeModule {
                 let im = Image::from_bytes(ctx, &bytes)?;
                 Ok(globals.new_handle(im)?.into())
             });
+            cls.sfunc(
+                "from_rgba8",
+                ["width", "height", "bytes"],
+                "Creates an image from raw pixels, 4 bytes (RGBA) per pixel",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let width = args.next().unwrap().u16()?;
+                    let height = args.next().unwrap().u16()?;
+                    let bytes = args.next().unwrap().convert::<Vec<u8>>(globals)?;
+                    let ctx = getctx(globals)?;
+                    let im = Image::from_rgba8(ctx, width, height, &bytes)?;
+                    Ok(globals.new_handle(im)?.into())
+                },
+            );
+            cls.sfunc(
+                "solid",
+                ["size", "color"],
+                "Creates a square image of the given size, filled with a single color",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let size = args.next().unwrap().u16()?;
+                    let color = Color::try_from(args.next().unwrap())?;
+                    let ctx = getctx(globals)?;
+                    let im = Image::solid(ctx, size, color.into())?;
+                    Ok(globals.new_handle(im)?.into())
+                },
+            );
             cls.ifunc("width", [], "", |owner, _globals, _, _| {
                 Ok(owner.borrow().get().width().into())
             });
             cls.ifunc("height", [], "", |owner, _globals, _, _| {
                 Ok(owner.borrow().get().height().into())
             });
+            cls.ifunc(
+                "sub_image",
+                ["rect"],
+                "Copies the given region (in pixels) of this image into a new Image",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let rect: ggez::graphics::Rect =
+                        args.next().unwrap().convert::<Rect>(globals)?.into();
+                    if rect.x < 0.0 || rect.y < 0.0 {
+                        return Err(rterr!("sub_image region must not be negative"));
+                    }
+                    let ctx = getctx(globals)?;
+                    let im = owner.borrow().sub_image(
+                        ctx,
+                        rect.x as u16,
+                        rect.y as u16,
+                        rect.w as u16,
+                        rect.h as u16,
+                    )?;
+                    Ok(globals.new_handle(im)?.into())
+                },
+            );
+            cls.ifunc(
+                "set_filter",
+                ["mode"],
+                "Sets the filter mode used when scaling this image ('nearest' or 'linear')",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let mode = FilterMode::try_from(args.next().unwrap())?;
+                    owner.borrow_mut().get_mut().set_filter(mode.into());
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("filter", [], "", |owner, _globals, _, _| {
+                Ok(FilterMode::from(owner.borrow().get().filter()).into())
+            });
+            cls.ifunc(
+                "set_wrap",
+                ArgSpec::builder().req("xmode").def("ymode", ()),
+                concat!(
+                    "Sets how the image is sampled outside of its bounds ",
+                    "('tile', 'mirror', 'clamp' or 'border'). ",
+                    "If ymode is not given, xmode is used for both",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let xmode = WrapMode::try_from(args.next().unwrap())?;
+                    let ymode = match args.next().unwrap() {
+                        Value::Nil => xmode,
+                        value => WrapMode::try_from(value)?,
+                    };
+                    owner
+                        .borrow_mut()
+                        .get_mut()
+                        .set_wrap(xmode.into(), ymode.into());
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("wrap", [], "", |owner, _globals, _, _| {
+                let (xmode, ymode) = owner.borrow().get().wrap();
+                Ok(vec![
+                    Value::from(WrapMode::from(xmode)),
+                    Value::from(WrapMode::from(ymode)),
+                ]
+                .into())
+            });
+            cls.ifunc(
+                "to_rgba8",
+                [],
+                "Returns the raw pixels of this image as bytes, 4 bytes (RGBA) per pixel",
+                |owner, globals, _, _| {
+                    let ctx = getctx(globals)?;
+                    let bytes = owner.borrow().to_rgba8(ctx)?;
+                    Ok(globals.new_handle::<Vec<u8>>(bytes)?.into())
+                },
+            );
+            cls.ifunc(
+                "encode",
+                ArgSpec::builder().def("format", "png"),
+                "Encodes this image as bytes in the given format ('png', 'jpeg' or 'bmp')",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let format = args.next().unwrap().into_string()?;
+                    let ctx = getctx(globals)?;
+                    let bytes = owner.borrow().encode(ctx, format.str())?;
+                    Ok(globals.new_handle::<Vec<u8>>(bytes)?.into())
+                },
+            );
+            cls.ifunc(
+                "frames",
+                ArgSpec::builder()
+                    .req("frame_width")
+                    .req("frame_height")
+                    .def("count", ())
+                    .def("margin", 0)
+                    .def("spacing", 0),
+                concat!(
+                    "Slices this image, as a uniform spritesheet, into frames of the given ",
+                    "size (in pixels).\n",
+                    "Returns a list of src rects in row-major order, for use with ",
+                    "draw, SpriteBatch.add or Animation.\n",
+                    "margin is the space around the whole sheet, and spacing is the space ",
+                    "between adjacent frames",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let frame_width = args.next().unwrap().u16()?;
+                    let frame_height = args.next().unwrap().u16()?;
+                    let count = match args.next().unwrap() {
+                        Value::Nil => None,
+                        value => Some(value.usize()?),
+                    };
+                    let margin = args.next().unwrap().u16()?;
+                    let spacing = args.next().unwrap().u16()?;
+                    let frames = grid_frames(
+                        owner.borrow().get(),
+                        frame_width,
+                        frame_height,
+                        margin,
+                        spacing,
+                        count,
+                    )?;
+                    Ok(frames
+                        .into_iter()
+                        .map(|rect| Value::from(Rect::from(rect)))
+                        .collect::<Vec<_>>()
+                        .into())
+                },
+            );
+        });
+        m.class::<AtlasBuilder, _>("AtlasBuilder", |cls| {
+            cls.doc(concat!(
+                "Packs many small images into a single Image (a texture atlas), ",
+                "so that they can be drawn from one SpriteBatch",
+            ));
+            cls.sfunc(
+                "__call",
+                ArgSpec::builder().def("padding", 1).def("max_width", 2048),
+                "padding is the number of transparent pixels left around each image",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let padding = args.next().unwrap().usize()? as u32;
+                    let max_width = args.next().unwrap().usize()? as u32;
+                    let builder = AtlasBuilder::new(padding, max_width);
+                    Ok(globals.new_handle(builder)?.into())
+                },
+            );
+            cls.ifunc(
+                "add_file",
+                ["name", "path"],
+                "",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let path = args.next().unwrap().into_string()?;
+                    owner.borrow_mut().add_file(name, path.str())?;
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "add_bytes",
+                ["name", "bytes"],
+                "",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let bytes = args.next().unwrap().convert::<Vec<u8>>(globals)?;
+                    owner.borrow_mut().add_bytes(name, &bytes)?;
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "add_image",
+                ["name", "image"],
+                "",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let image = args.next().unwrap().into_handle::<Image>()?;
+                    let image = image.borrow();
+                    let ctx = getctx(globals)?;
+                    let pixels = image.to_rgba8(ctx)?;
+                    let (width, height) = (image.get().width(), image.get().height());
+                    let im = match image::RgbaImage::from_raw(width as u32, height as u32, pixels) {
+                        Some(im) => im,
+                        None => {
+                            return Err(rterr!("Image pixel data does not match its dimensions"))
+                        }
+                    };
+                    owner.borrow_mut().add_rgba(name, im)?;
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("len", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().len().into())
+            });
+            cls.ifunc("build", [], "", |owner, globals, _, _| {
+                let ctx = getctx(globals)?;
+                let atlas = owner.borrow().build(ctx)?;
+                Ok(globals.new_handle(atlas)?.into())
+            });
+        });
+        m.class::<Atlas, _>("Atlas", |cls| {
+            cls.doc("A packed Image, and the named regions of it");
+            cls.ifunc("image", [], "", |owner, globals, _, _| {
+                let image = owner.borrow().image().clone();
+                Ok(globals.new_handle(image)?.into())
+            });
+            cls.ifunc("names", [], "", |owner, _globals, _, _| {
+                Ok(owner
+                    .borrow()
+                    .names()
+                    .into_iter()
+                    .map(Value::from)
+                    .collect::<Vec<_>>()
+                    .into())
+            });
+            cls.ifunc(
+                "region",
+                ["name"],
+                concat!(
+                    "Returns the named region as a normalized [x, y, w, h] rect, ",
+                    "usable as the src of draw or SpriteBatch.add",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    Ok(Rect::from(owner.borrow().region(&name)?).into())
+                },
+            );
+            cls.ifunc(
+                "pixel_region",
+                ["name"],
+                "Returns the named region as [x, y, w, h], in pixels",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    Ok(Rect::from(owner.borrow().pixel_region(&name)?).into())
+                },
+            );
+        });
+        m.class::<Animation, _>("Animation", |cls| {
+            cls.doc(concat!(
+                "Plays through a list of src rects (e.g. from Image.frames), ",
+                "advancing as update(dt) is called",
+            ));
+            cls.sfunc(
+                "__call",
+                ArgSpec::builder()
+                    .req("frames")
+                    .req("frame_time")
+                    .def("loop", true),
+                concat!(
+                    "frame_time is either the number of seconds each frame is shown, ",
+                    "or a list with the duration of each frame",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let frames = args
+                        .next()
+                        .unwrap()
+                        .into_list()?
+                        .borrow()
+                        .iter()
+                        .map(|value| Ok(value.convert::<Rect>(globals)?.into()))
+                        .collect::<Result<Vec<ggez::graphics::Rect>>>()?;
+                    let durations = match args.next().unwrap() {
+                        Value::List(list) => list
+                            .borrow()
+                            .iter()
+                            .map(|value| value.f32())
+                            .collect::<Result<Vec<_>>>()?,
+                        value => vec![value.f32()?; frames.len()],
+                    };
+                    let looping = args.next().unwrap().truthy();
+                    let animation = Animation::new(frames, durations, looping)?;
+                    Ok(globals.new_handle(animation)?.into())
+                },
+            );
+            cls.ifunc("update", ["dt"], "", |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let dt = args.next().unwrap().f32()?;
+                owner.borrow_mut().update(dt);
+                Ok(Value::Nil)
+            });
+            cls.ifunc(
+                "frame",
+                [],
+                "Returns the src rect of the current frame",
+                |owner, _globals, _, _| Ok(Rect::from(owner.borrow().frame()).into()),
+            );
+            cls.ifunc("index", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().index().into())
+            });
+            cls.ifunc("set_index", ["index"], "", |owner, _globals, args, _| {
+                let mut args = args.into_iter();
+                let index = args.next().unwrap().usize()?;
+                owner.borrow_mut().set_index(index)?;
+                Ok(Value::Nil)
+            });
+            cls.ifunc("len", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().len().into())
+            });
+            cls.ifunc("playing", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().playing().into())
+            });
+            cls.ifunc("play", [], "", |owner, _globals, _, _| {
+                owner.borrow_mut().set_playing(true);
+                Ok(Value::Nil)
+            });
+            cls.ifunc("pause", [], "", |owner, _globals, _, _| {
+                owner.borrow_mut().set_playing(false);
+                Ok(Value::Nil)
+            });
+            cls.ifunc(
+                "finished",
+                [],
+                "Whether a non-looping animation has finished its last frame",
+                |owner, _globals, _, _| Ok(owner.borrow().finished().into()),
+            );
+            cls.ifunc("reset", [], "", |owner, _globals, _, _| {
+                owner.borrow_mut().reset();
+                Ok(Value::Nil)
+            });
+        });
+        m.class::<TileMap, _>("TileMap", |cls| {
+            cls.doc(concat!(
+                "A map made with the Tiled map editor.\n",
+                "Each tile layer is drawn with SpriteBatches (one per tileset it uses), ",
+                "and object layers are available as lists of maps, e.g. for spawning entities",
+            ));
+            cls.sfunc(
+                "from_file",
+                ["path"],
+                concat!(
+                    "Loads a .tmx or .json map. Tileset images (and external tilesets) ",
+                    "are loaded relative to the map file",
+                ),
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let path = args.next().unwrap().into_string()?;
+                    let map = TileMap::from_file(globals, path.str())?;
+                    Ok(globals.new_handle(map)?.into())
+                },
+            );
+            cls.ifunc(
+                "width",
+                [],
+                "Width of the map, in tiles",
+                |owner, _globals, _, _| Ok((owner.borrow().data().width as usize).into()),
+            );
+            cls.ifunc(
+                "height",
+                [],
+                "Height of the map, in tiles",
+                |owner, _globals, _, _| Ok((owner.borrow().data().height as usize).into()),
+            );
+            cls.ifunc("tile_width", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().data().tile_width as usize).into())
+            });
+            cls.ifunc("tile_height", [], "", |owner, _globals, _, _| {
+                Ok((owner.borrow().data().tile_height as usize).into())
+            });
+            cls.ifunc("properties", [], "", |owner, _globals, _, _| {
+                Ok(properties_to_value(&owner.borrow().data().properties))
+            });
+            cls.ifunc(
+                "layers",
+                [],
+                concat!(
+                    "Returns a map for each layer, in drawing order, with keys ",
+                    "'name', 'kind' ('tiles' or 'objects'), 'visible' and 'properties'",
+                ),
+                |owner, _globals, _, _| {
+                    let owner = owner.borrow();
+                    let layers: Vec<Value> = owner
+                        .data()
+                        .layers
+                        .iter()
+                        .map(|layer| {
+                            let (kind, visible, properties) = match layer {
+                                Layer::Tiles(l) => ("tiles", l.visible, &l.properties),
+                                Layer::Objects(l) => ("objects", l.visible, &l.properties),
+                            };
+                            vec![
+                                ("name", Value::from(layer.name().to_owned())),
+                                ("kind", Value::from(kind)),
+                                ("visible", Value::from(visible)),
+                                ("properties", properties_to_value(properties)),
+                            ]
+                            .into_iter()
+                            .map(|(k, v)| (Key::from(k.to_owned()), v))
+                            .collect::<Map>()
+                            .into()
+                        })
+                        .collect();
+                    Ok(layers.into())
+                },
+            );
+            cls.ifunc(
+                "batches",
+                ["layer"],
+                "Returns the SpriteBatches used to draw the given tile layer",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let owner = owner.borrow();
+                    let index = owner.layer_index(name.str())?;
+                    Ok(owner
+                        .batches(index)
+                        .iter()
+                        .map(|batch| Value::from(batch.clone()))
+                        .collect::<Vec<_>>()
+                        .into())
+                },
+            );
+            cls.ifunc(
+                "draw_layer",
+                ArgSpec::builder().req("layer").def("x", 0).def("y", 0),
+                "Draws the given tile layer, with the map's top left corner at (x, y)",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let x = f32::try_from(args.next().unwrap())?;
+                    let y = f32::try_from(args.next().unwrap())?;
+                    let owner = owner.borrow();
+                    let index = owner.layer_index(name.str())?;
+                    for batch in owner.batches(index) {
+                        let param = DrawParam::default().dest([x, y]);
+                        draw_value(globals, batch.clone().into(), param)?;
+                    }
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "draw",
+                ArgSpec::builder().def("x", 0).def("y", 0),
+                "Draws all visible tile layers, with the map's top left corner at (x, y)",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let x = f32::try_from(args.next().unwrap())?;
+                    let y = f32::try_from(args.next().unwrap())?;
+                    let owner = owner.borrow();
+                    for (index, layer) in owner.data().layers.iter().enumerate() {
+                        if let Layer::Tiles(layer) = layer {
+                            if !layer.visible {
+                                continue;
+                            }
+                            for batch in owner.batches(index) {
+                                let param = DrawParam::default().dest([x, y]);
+                                draw_value(globals, batch.clone().into(), param)?;
+                            }
+                        }
+                    }
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "tile",
+                ["layer", "x", "y"],
+                concat!(
+                    "Returns the gid of the tile at (x, y) (in tiles) of the given layer, ",
+                    "or nil if there is no tile there",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let x = args.next().unwrap().usize()? as u32;
+                    let y = args.next().unwrap().usize()? as u32;
+                    let owner = owner.borrow();
+                    match owner.tile_layer(name.str())?.gid(x, y) {
+                        Some(gid) if gid.gid != 0 => Ok((gid.gid as usize).into()),
+                        _ => Ok(Value::Nil),
+                    }
+                },
+            );
+            cls.ifunc(
+                "tile_properties",
+                ["gid"],
+                "Returns the custom properties of the tile with the given gid, or nil",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let gid = args.next().unwrap().usize()? as u32;
+                    Ok(match owner.borrow().tile_properties(gid) {
+                        Some(props) => properties_to_value(props),
+                        None => Value::Nil,
+                    })
+                },
+            );
+            cls.ifunc(
+                "objects",
+                ["layer"],
+                concat!(
+                    "Returns the objects of the given object layer, as maps with keys ",
+                    "'id', 'name', 'type', 'shape', 'x', 'y', 'width', 'height', ",
+                    "'rotation', 'visible' and 'properties', ",
+                    "plus 'gid' for tile objects and 'points' for polygons and polylines",
+                ),
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let name = args.next().unwrap().into_string()?;
+                    let owner = owner.borrow();
+                    Ok(owner
+                        .object_layer(name.str())?
+                        .objects
+                        .iter()
+                        .map(Value::from)
+                        .collect::<Vec<_>>()
+                        .into())
+                },
+            );
+        });
+        m.class::<Canvas, _>("Canvas", |cls| {
+            cls.doc(concat!(
+                "An offscreen render target. Canvases can be drawn ",
+                "with draw like any other drawable",
+            ));
+            cls.sfunc(
+                "__call",
+                ArgSpec::builder()
+                    .def("width", ())
+                    .def("height", ())
+                    .def("samples", 1),
+                "If width and height are not given, the canvas is the size of the window",
+                |globals, args, _| {
+                    let mut args = args.into_iter();
+                    let width = args.next().unwrap();
+                    let height = args.next().unwrap();
+                    let samples = NumSamples::try_from(args.next().unwrap())?;
+                    let ctx = getctx(globals)?;
+                    let canvas = if width.is_nil() && height.is_nil() {
+                        Canvas::with_window_size(ctx)?
+                    } else {
+                        let width = width.u16()?;
+                        let height = height.u16()?;
+                        Canvas::new(ctx, width, height, samples.into())?
+                    };
+                    Ok(globals.new_handle(canvas)?.into())
+                },
+            );
+            cls.ifunc("width", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().get().image().width().into())
+            });
+            cls.ifunc("height", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().get().image().height().into())
+            });
+            cls.ifunc(
+                "image",
+                [],
+                concat!(
+                    "Returns the Image backing this Canvas. Note that the image ",
+                    "is stored upside down, so drawing the Canvas itself is ",
+                    "usually what you want",
+                ),
+                |owner, globals, _, _| {
+                    let image = Image::from(owner.borrow().get().image().clone());
+                    Ok(globals.new_handle(image)?.into())
+                },
+            );
         });
         m.class::<SpriteIdx, _>("SpriteIdx", |_| {});
         m.class::<SpriteBatch, _>("SpriteBatch", |cls| {
@@ -420,31 +1736,86 @@ pub(in super::super) fn new() -> NativeModule {
                 "",
                 |owner, globals, args, _| {
                     let mut args = args.into_iter();
-                    let x = f32::try_from(args.next().unwrap())?;
-                    let y = f32::try_from(args.next().unwrap())?;
-                    let src = args.next().unwrap().convert::<Rect>(globals)?;
-                    let rotation = f32::try_from(args.next().unwrap())?;
-                    let xscale = f32::try_from(args.next().unwrap())?;
-                    let yscale = f32::try_from(args.next().unwrap())?;
-                    let xoffset = f32::try_from(args.next().unwrap())?;
-                    let yoffset = f32::try_from(args.next().unwrap())?;
-                    let colorval = args.next().unwrap();
-
-                    let drawparam = DrawParam::default()
-                        .src(src.into())
-                        .dest([x, y])
-                        .rotation(rotation)
-                        .scale([xscale, yscale])
-                        .offset([xoffset, yoffset])
-                        .color(if colorval.is_nil() {
-                            ggez::graphics::WHITE
-                        } else {
-                            Color::try_from(colorval)?.into()
-                        });
-
-                    let index = owner.borrow_mut().get_mut().add(drawparam);
-
-                    Ok(globals.new_handle::<SpriteIdx>(index.into())?.into())
+                    let drawparam = sprite_param_from_args(globals, &mut args)?;
+                    let index = owner.borrow_mut().add(drawparam);
+                    Ok(globals.new_handle::<SpriteIdx>(index)?.into())
+                },
+            );
+            cls.ifunc(
+                "add_many",
+                ["params"],
+                concat!(
+                    "Adds a sprite for each entry in params, where each entry is ",
+                    "either an [x, y] pair, or a map with the same keys as the ",
+                    "arguments to add. Returns the list of SpriteIdx",
+                ),
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let params = args.next().unwrap().into_list()?;
+                    let params: Vec<Value> = params.borrow().iter().cloned().collect();
+                    let mut indices = Vec::new();
+                    for param in params {
+                        let drawparam = sprite_param_from_value(globals, param)?;
+                        let index = owner.borrow_mut().add(drawparam);
+                        indices.push(Value::from(globals.new_handle::<SpriteIdx>(index)?));
+                    }
+                    Ok(indices.into())
+                },
+            );
+            cls.ifunc(
+                "set",
+                ArgSpec::builder()
+                    .req("idx")
+                    .req("x")
+                    .req("y")
+                    .def("src", [0, 0, 1, 1])
+                    .def("rotation", 0)
+                    .def("xscale", 1)
+                    .def("yscale", 1)
+                    .def("xoffset", 0)
+                    .def("yoffset", 0)
+                    .def("color", ()),
+                "Replaces the parameters of the sprite at the given SpriteIdx",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let index = args.next().unwrap().convert::<SpriteIdx>(globals)?;
+                    let drawparam = sprite_param_from_args(globals, &mut args)?;
+                    owner.borrow_mut().set(index, drawparam)?;
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "clear",
+                [],
+                "Removes all sprites. Existing SpriteIdx values become invalid",
+                |owner, _globals, _, _| {
+                    owner.borrow_mut().clear();
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc("len", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().len().into())
+            });
+            cls.ifunc(
+                "set_image",
+                ["image"],
+                "Replaces the image used by all sprites in this batch",
+                |owner, globals, args, _| {
+                    let mut args = args.into_iter();
+                    let image = args.next().unwrap().convert::<Image>(globals)?;
+                    owner.borrow_mut().get_mut().set_image(image.into());
+                    Ok(Value::Nil)
+                },
+            );
+            cls.ifunc(
+                "set_filter",
+                ["mode"],
+                "Sets the filter mode used when drawing this batch ('nearest' or 'linear')",
+                |owner, _globals, args, _| {
+                    let mut args = args.into_iter();
+                    let mode = FilterMode::try_from(args.next().unwrap())?;
+                    owner.borrow_mut().get_mut().set_filter(mode.into());
+                    Ok(Value::Nil)
                 },
             );
         });
@@ -0,0 +1,58 @@
+use super::*;
+
+/// Wrapper around DrawParam, so that a set of drawing parameters
+/// can be created once and reused across draw calls
+#[derive(Clone, Copy)]
+pub struct DrawParamW(DrawParam);
+
+impl DrawParamW {
+    pub fn get(&self) -> &DrawParam {
+        &self.0
+    }
+    pub fn get_mut(&mut self) -> &mut DrawParam {
+        &mut self.0
+    }
+}
+
+impl From<DrawParamW> for DrawParam {
+    fn from(x: DrawParamW) -> Self {
+        x.0
+    }
+}
+
+impl From<DrawParam> for DrawParamW {
+    fn from(x: DrawParam) -> Self {
+        Self(x)
+    }
+}
+
+impl ConvertValue for DrawParamW {}
+
+/// Reads the (x, y, rotation, xscale, yscale, xoffset, yoffset, color, src)
+/// arguments shared by draw, print and draw_queued_text
+pub(super) fn drawparam_from_args<I: Iterator<Item = Value>>(
+    globals: &mut Globals,
+    args: &mut I,
+) -> Result<DrawParam> {
+    let x = f32::try_from(args.next().unwrap())?;
+    let y = f32::try_from(args.next().unwrap())?;
+    let rotation = f32::try_from(args.next().unwrap())?;
+    let xscale = f32::try_from(args.next().unwrap())?;
+    let yscale = f32::try_from(args.next().unwrap())?;
+    let xoffset = f32::try_from(args.next().unwrap())?;
+    let yoffset = f32::try_from(args.next().unwrap())?;
+    let colorval = args.next().unwrap();
+    let src = args.next().unwrap().convert::<Rect>(globals)?;
+
+    Ok(DrawParam::default()
+        .dest([x, y])
+        .rotation(rotation)
+        .scale([xscale, yscale])
+        .offset([xoffset, yoffset])
+        .color(if colorval.is_nil() {
+            ggez::graphics::WHITE
+        } else {
+            Color::try_from(colorval)?.into()
+        })
+        .src(src.into()))
+}
@@ -1,23 +1,44 @@
-use crate::ConvertValue;
+use super::*;
 
-pub struct SpriteBatch(ggez::graphics::spritebatch::SpriteBatch);
+pub struct SpriteBatch {
+    sb: ggez::graphics::spritebatch::SpriteBatch,
+    len: usize,
+}
 
 impl SpriteBatch {
     pub fn new(sb: ggez::graphics::spritebatch::SpriteBatch) -> Self {
-        Self(sb)
+        Self { sb, len: 0 }
     }
     pub fn get_mut(&mut self) -> &mut ggez::graphics::spritebatch::SpriteBatch {
-        &mut self.0
+        &mut self.sb
     }
     pub fn get(&self) -> &ggez::graphics::spritebatch::SpriteBatch {
-        &self.0
+        &self.sb
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn add(&mut self, param: DrawParam) -> SpriteIdx {
+        self.len += 1;
+        self.sb.add(param).into()
+    }
+    pub fn set(&mut self, index: SpriteIdx, param: DrawParam) -> Result<()> {
+        mtry!(self.sb.set(index.into(), param));
+        Ok(())
+    }
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.sb.clear();
     }
 }
 
 impl ConvertValue for SpriteBatch {}
 
+#[derive(Clone, Copy)]
 pub struct SpriteIdx(ggez::graphics::spritebatch::SpriteIdx);
 
+impl ConvertValue for SpriteIdx {}
+
 impl From<SpriteIdx> for ggez::graphics::spritebatch::SpriteIdx {
     fn from(index: SpriteIdx) -> Self {
         index.0
@@ -29,3 +50,93 @@ impl From<ggez::graphics::spritebatch::SpriteIdx> for SpriteIdx {
         Self(index)
     }
 }
+
+/// Reads the (x, y, src, rotation, xscale, yscale, xoffset, yoffset, color)
+/// arguments shared by SpriteBatch.add and SpriteBatch.set
+pub(super) fn sprite_param_from_args<I: Iterator<Item = Value>>(
+    globals: &mut Globals,
+    args: &mut I,
+) -> Result<DrawParam> {
+    let x = f32::try_from(args.next().unwrap())?;
+    let y = f32::try_from(args.next().unwrap())?;
+    let src = args.next().unwrap().convert::<Rect>(globals)?;
+    let rotation = f32::try_from(args.next().unwrap())?;
+    let xscale = f32::try_from(args.next().unwrap())?;
+    let yscale = f32::try_from(args.next().unwrap())?;
+    let xoffset = f32::try_from(args.next().unwrap())?;
+    let yoffset = f32::try_from(args.next().unwrap())?;
+    let colorval = args.next().unwrap();
+
+    Ok(DrawParam::default()
+        .src(src.into())
+        .dest([x, y])
+        .rotation(rotation)
+        .scale([xscale, yscale])
+        .offset([xoffset, yoffset])
+        .color(if colorval.is_nil() {
+            ggez::graphics::WHITE
+        } else {
+            Color::try_from(colorval)?.into()
+        }))
+}
+
+/// Like sprite_param_from_args, but the parameters are given as
+/// either an [x, y] pair, or a map with the same keys as the arguments
+/// to SpriteBatch.add
+pub(super) fn sprite_param_from_value(globals: &mut Globals, value: Value) -> Result<DrawParam> {
+    match value {
+        Value::List(_) => {
+            let [x, y] = <[f32; 2]>::try_from(value)?;
+            Ok(DrawParam::default().dest([x, y]))
+        }
+        Value::Map(map) => {
+            let mut map = map.to_string_keys()?;
+            let mut param = DrawParam::default();
+            let x = match map.remove("x") {
+                Some(value) => f32::try_from(value)?,
+                None => 0.0,
+            };
+            let y = match map.remove("y") {
+                Some(value) => f32::try_from(value)?,
+                None => 0.0,
+            };
+            param = param.dest([x, y]);
+            if let Some(value) = map.remove("src") {
+                param = param.src(value.convert::<Rect>(globals)?.into());
+            }
+            if let Some(value) = map.remove("rotation") {
+                param = param.rotation(f32::try_from(value)?);
+            }
+            let xscale = match map.remove("xscale") {
+                Some(value) => f32::try_from(value)?,
+                None => 1.0,
+            };
+            let yscale = match map.remove("yscale") {
+                Some(value) => f32::try_from(value)?,
+                None => 1.0,
+            };
+            param = param.scale([xscale, yscale]);
+            let xoffset = match map.remove("xoffset") {
+                Some(value) => f32::try_from(value)?,
+                None => 0.0,
+            };
+            let yoffset = match map.remove("yoffset") {
+                Some(value) => f32::try_from(value)?,
+                None => 0.0,
+            };
+            param = param.offset([xoffset, yoffset]);
+            if let Some(value) = map.remove("color") {
+                param = param.color(Color::try_from(value)?.into());
+            }
+            if !map.is_empty() {
+                let keys: Vec<_> = map.keys().collect();
+                return Err(rterr!("Unused sprite attributes: {:?}", keys));
+            }
+            Ok(param)
+        }
+        _ => Err(rterr!(
+            "Expected sprite parameters ([x, y] or a map), but got {:?}",
+            value
+        )),
+    }
+}
@@ -0,0 +1,203 @@
+use super::*;
+use ggez::graphics::BlendMode;
+use ggez::graphics::Rect;
+use ggez::graphics::TextFragment as GgezFragment;
+
+/// The layout settings of a Text that ggez doesn't let us read back,
+/// needed to lay out each line separately when the line spacing isn't 1
+#[derive(Clone, Copy)]
+pub struct TextLayout {
+    bounds: Option<([f32; 2], ggez::graphics::Align)>,
+    font: Option<(ggez::graphics::Font, ggez::graphics::Scale)>,
+    line_spacing: f32,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            bounds: None,
+            font: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl Text {
+    pub fn set_bounds(&mut self, bounds: [f32; 2], align: ggez::graphics::Align) {
+        self.0.set_bounds(bounds, align);
+        self.1.bounds = Some((bounds, align));
+    }
+    pub fn set_font(&mut self, font: ggez::graphics::Font, scale: ggez::graphics::Scale) {
+        self.0.set_font(font, scale);
+        self.1.font = Some((font, scale));
+    }
+    pub fn line_spacing(&self) -> f32 {
+        self.1.line_spacing
+    }
+    /// Sets the distance between the tops of consecutive lines,
+    /// as a multiple of the line height
+    pub fn set_line_spacing(&mut self, spacing: f32) -> Result<()> {
+        if !(spacing >= 0.0) || !spacing.is_finite() {
+            return Err(rterr!(
+                "Line spacing must be a non-negative number, but got {}",
+                spacing
+            ));
+        }
+        self.1.line_spacing = spacing;
+        Ok(())
+    }
+
+    /// A new ggez Text with the given fragments, laid out like this one,
+    /// except that it may be arbitrarily tall
+    fn text_of(&self, fragments: Vec<GgezFragment>) -> ggez::graphics::Text {
+        let mut text = ggez::graphics::Text::default();
+        for fragment in fragments {
+            text.add(fragment);
+        }
+        if let Some((font, scale)) = self.1.font {
+            text.set_font(font, scale);
+        }
+        if let Some(([width, _], align)) = self.1.bounds {
+            text.set_bounds([width, std::f32::INFINITY], align);
+        }
+        text.set_blend_mode(self.0.blend_mode());
+        text
+    }
+
+    /// Each line of the text as its own ggez Text, with its y offset.
+    /// Lines end at newlines, and where the text wraps to fit its bounds
+    pub fn lines(&self, ctx: &ggez::Context) -> Vec<(f32, ggez::graphics::Text)> {
+        let mut paragraphs = vec![Vec::new()];
+        for fragment in self.0.fragments() {
+            for (i, piece) in fragment.text.split('\n').enumerate() {
+                if i > 0 {
+                    paragraphs.push(Vec::new());
+                }
+                if !piece.is_empty() {
+                    let mut fragment = fragment.clone();
+                    fragment.text = piece.to_owned();
+                    paragraphs.last_mut().unwrap().push(fragment);
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        for paragraph in paragraphs {
+            if self.1.bounds.is_some() {
+                lines.extend(self.wrap(ctx, paragraph));
+            } else {
+                lines.push(paragraph);
+            }
+        }
+        let lines: Vec<_> = lines.into_iter().map(|line| self.text_of(line)).collect();
+        let line_height = lines
+            .iter()
+            .map(|line| line.height(ctx) as f32)
+            .fold(0.0, f32::max);
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (i as f32 * line_height * self.1.line_spacing, line))
+            .collect()
+    }
+
+    /// Splits a paragraph where ggez wraps it, going by where the y
+    /// position of its glyphs changes
+    fn wrap(&self, ctx: &ggez::Context, paragraph: Vec<GgezFragment>) -> Vec<Vec<GgezFragment>> {
+        let chars: Vec<(usize, char)> = paragraph
+            .iter()
+            .enumerate()
+            .flat_map(|(i, fragment)| fragment.text.chars().map(move |c| (i, c)))
+            .collect();
+        let text = self.text_of(paragraph.clone());
+        let positions: Vec<f32> = text.glyph_positions(ctx).iter().map(|p| p.y).collect();
+        if positions.len() != chars.len() {
+            // No reliable way to tell which glyph is which character
+            return vec![paragraph];
+        }
+        // Each piece of a fragment on a line, with the index of the fragment
+        let mut lines: Vec<Vec<(usize, GgezFragment)>> = vec![Vec::new()];
+        for (j, (i, c)) in chars.into_iter().enumerate() {
+            if j > 0 && positions[j] != positions[j - 1] {
+                lines.push(Vec::new());
+            }
+            let line = lines.last_mut().unwrap();
+            match line.last_mut() {
+                Some((last, piece)) if *last == i => piece.text.push(c),
+                _ => {
+                    let mut piece = paragraph[i].clone();
+                    piece.text = c.to_string();
+                    line.push((i, piece));
+                }
+            }
+        }
+        lines
+            .into_iter()
+            .map(|line| line.into_iter().map(|(_, piece)| piece).collect())
+            .collect()
+    }
+
+    fn spaced(&self) -> bool {
+        self.1.line_spacing != 1.0
+    }
+
+    pub fn width(&self, ctx: &ggez::Context) -> f32 {
+        if !self.spaced() {
+            return self.0.width(ctx) as f32;
+        }
+        self.lines(ctx)
+            .iter()
+            .map(|(_, line)| line.width(ctx) as f32)
+            .fold(0.0, f32::max)
+    }
+
+    pub fn height(&self, ctx: &ggez::Context) -> f32 {
+        if !self.spaced() {
+            return self.0.height(ctx) as f32;
+        }
+        match self.lines(ctx).last() {
+            Some((y, line)) => y + line.height(ctx) as f32,
+            None => 0.0,
+        }
+    }
+
+    /// Queues each line for draw_queued_text, at the given position
+    pub fn queue(
+        &self,
+        ctx: &mut ggez::Context,
+        [x, y]: [f32; 2],
+        color: Option<ggez::graphics::Color>,
+    ) {
+        if !self.spaced() {
+            ggez::graphics::queue_text(ctx, &self.0, [x, y], color);
+            return;
+        }
+        for (dy, line) in self.lines(ctx) {
+            ggez::graphics::queue_text(ctx, &line, [x, y + dy], color);
+        }
+    }
+}
+
+impl Drawable for Text {
+    fn draw(&self, ctx: &mut ggez::Context, param: DrawParam) -> ggez::GameResult {
+        if !self.spaced() {
+            return self.0.draw(ctx, param);
+        }
+        let matrix: ggez::nalgebra::Matrix4<f32> = param.to_matrix().into();
+        for (y, line) in self.lines(ctx) {
+            line.draw(ctx, param.transform(matrix * translation(0.0, y)))?;
+        }
+        Ok(())
+    }
+    fn dimensions(&self, ctx: &mut ggez::Context) -> Option<Rect> {
+        if !self.spaced() {
+            return Drawable::dimensions(&self.0, ctx);
+        }
+        Some(Rect::new(0.0, 0.0, self.width(ctx), self.height(ctx)))
+    }
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.0.set_blend_mode(mode);
+    }
+    fn blend_mode(&self) -> Option<BlendMode> {
+        self.0.blend_mode()
+    }
+}
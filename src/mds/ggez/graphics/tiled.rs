@@ -0,0 +1,764 @@
+//! Loading of maps made with the Tiled map editor (https://www.mapeditor.org)
+//!
+//! Both the XML (.tmx/.tsx) and JSON formats are supported, for finite,
+//! orthogonal maps whose tilesets each use a single image.
+//! Tile data may be csv, xml or uncompressed base64 encoded.
+use super::*;
+use crate::Key;
+use crate::Map;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY);
+
+#[derive(Clone)]
+pub enum Property {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl From<&Property> for Value {
+    fn from(p: &Property) -> Self {
+        match p {
+            Property::Bool(b) => Value::from(*b),
+            Property::Number(x) => Value::from(*x),
+            Property::String(s) => Value::from(s.clone()),
+        }
+    }
+}
+
+pub type Properties = Vec<(String, Property)>;
+
+pub fn properties_to_value(props: &Properties) -> Value {
+    props
+        .iter()
+        .map(|(k, v)| (Key::from(k.clone()), Value::from(v)))
+        .collect::<Map>()
+        .into()
+}
+
+fn parse_property(type_: &str, value: &str) -> Result<Property> {
+    Ok(match type_ {
+        "bool" => Property::Bool(value == "true"),
+        "int" | "float" => Property::Number(parse_str(value)?),
+        _ => Property::String(value.to_owned()),
+    })
+}
+
+pub struct Tileset {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub image: PathBuf,
+    pub tiles: HashMap<u32, Properties>,
+}
+
+impl Tileset {
+    /// Returns the region of the tileset image (in pixels) of the tile
+    /// with the given local id
+    pub fn tile_rect(&self, id: u32) -> ggez::graphics::Rect {
+        let columns = self.columns.max(1);
+        let x = self.margin + (id % columns) * (self.tile_width + self.spacing);
+        let y = self.margin + (id / columns) * (self.tile_height + self.spacing);
+        ggez::graphics::Rect::new(
+            x as f32,
+            y as f32,
+            self.tile_width as f32,
+            self.tile_height as f32,
+        )
+    }
+}
+
+/// A global tile id, with the flip flags separated out
+#[derive(Clone, Copy)]
+pub struct Gid {
+    pub gid: u32,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_d: bool,
+}
+
+impl From<u32> for Gid {
+    fn from(raw: u32) -> Self {
+        Self {
+            gid: raw & GID_MASK,
+            flip_h: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_v: raw & FLIPPED_VERTICALLY != 0,
+            flip_d: raw & FLIPPED_DIAGONALLY != 0,
+        }
+    }
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u32>,
+    pub visible: bool,
+    pub opacity: f32,
+    pub offset: [f32; 2],
+    pub properties: Properties,
+}
+
+impl TileLayer {
+    pub fn gid(&self, x: u32, y: u32) -> Option<Gid> {
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            Some(self.data[(y * self.width + x) as usize].into())
+        }
+    }
+}
+
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    pub type_: String,
+    pub shape: &'static str,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+    pub gid: Option<u32>,
+    pub visible: bool,
+    pub points: Vec<[f32; 2]>,
+    pub properties: Properties,
+}
+
+impl From<&Object> for Value {
+    fn from(object: &Object) -> Self {
+        let mut pairs = vec![
+            ("id", Value::from(object.id as f64)),
+            ("name", Value::from(object.name.clone())),
+            ("type", Value::from(object.type_.clone())),
+            ("shape", Value::from(object.shape)),
+            ("x", Value::from(object.x as f64)),
+            ("y", Value::from(object.y as f64)),
+            ("width", Value::from(object.width as f64)),
+            ("height", Value::from(object.height as f64)),
+            ("rotation", Value::from(object.rotation as f64)),
+            ("visible", Value::from(object.visible)),
+            ("properties", properties_to_value(&object.properties)),
+        ];
+        if let Some(gid) = object.gid {
+            pairs.push(("gid", Value::from((gid & GID_MASK) as f64)));
+        }
+        if !object.points.is_empty() {
+            let points: Vec<Value> = object
+                .points
+                .iter()
+                .map(|[x, y]| vec![Value::from(*x as f64), Value::from(*y as f64)].into())
+                .collect();
+            pairs.push(("points", points.into()));
+        }
+        pairs
+            .into_iter()
+            .map(|(k, v)| (Key::from(k.to_owned()), v))
+            .collect::<Map>()
+            .into()
+    }
+}
+
+pub struct ObjectLayer {
+    pub name: String,
+    pub objects: Vec<Object>,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+pub enum Layer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+}
+
+impl Layer {
+    pub fn name(&self) -> &str {
+        match self {
+            Layer::Tiles(layer) => &layer.name,
+            Layer::Objects(layer) => &layer.name,
+        }
+    }
+}
+
+pub struct MapData {
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<Layer>,
+    pub properties: Properties,
+}
+
+impl MapData {
+    /// Loads a .json map if the path ends in '.json', and a .tmx map otherwise
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let mut data = if is_json(path) {
+            json::map(path)?
+        } else {
+            tmx::map(path)?
+        };
+        data.tilesets.sort_by_key(|ts| ts.first_gid);
+        for layer in &data.layers {
+            if let Layer::Tiles(layer) = layer {
+                if layer.data.len() != (layer.width * layer.height) as usize {
+                    return Err(rterr!(
+                        "Layer {:?} has {} tiles, but is {}x{}",
+                        layer.name,
+                        layer.data.len(),
+                        layer.width,
+                        layer.height
+                    ));
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Returns the index of the tileset the given gid belongs to
+    pub fn tileset_index(&self, gid: u32) -> Option<usize> {
+        if gid == 0 {
+            return None;
+        }
+        self.tilesets.iter().rposition(|ts| ts.first_gid <= gid)
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().map(|e| e == "json").unwrap_or(false)
+}
+
+/// Loads a tileset stored in its own .tsx or .json file
+fn external_tileset(path: &Path, first_gid: u32) -> Result<Tileset> {
+    if is_json(path) {
+        json::tileset_file(path, first_gid)
+    } else {
+        tmx::tileset_file(path, first_gid)
+    }
+}
+
+fn parse_str<T: FromStr>(s: &str) -> Result<T>
+where
+    T::Err: std::fmt::Debug,
+{
+    match s.trim().parse() {
+        Ok(x) => Ok(x),
+        Err(e) => Err(rterr!("Invalid value {:?} in tile map: {:?}", s, e)),
+    }
+}
+
+fn base64_gids(text: &str, compression: Option<&str>) -> Result<Vec<u32>> {
+    if let Some(compression) = compression {
+        if !compression.is_empty() {
+            return Err(rterr!(
+                "Compressed ({}) tile layer data is not supported",
+                compression
+            ));
+        }
+    }
+    let bytes = mtry!(base64::decode(text.trim()));
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+mod tmx {
+    use super::*;
+    type Node<'a, 'input> = roxmltree::Node<'a, 'input>;
+
+    fn attr<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str> {
+        match node.attribute(name) {
+            Some(value) => Ok(value),
+            None => Err(rterr!(
+                "Missing attribute {:?} on <{}>",
+                name,
+                node.tag_name().name()
+            )),
+        }
+    }
+
+    fn attr_parse<T: FromStr>(node: Node, name: &str) -> Result<T>
+    where
+        T::Err: std::fmt::Debug,
+    {
+        parse_str(attr(node, name)?)
+    }
+
+    fn attr_or<T: FromStr>(node: Node, name: &str, default: T) -> Result<T>
+    where
+        T::Err: std::fmt::Debug,
+    {
+        match node.attribute(name) {
+            Some(value) => parse_str(value),
+            None => Ok(default),
+        }
+    }
+
+    fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        node.children()
+            .find(|c| c.is_element() && c.tag_name().name() == name)
+    }
+
+    fn read_doc(path: &Path) -> Result<String> {
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    pub(super) fn map(path: &Path) -> Result<MapData> {
+        let text = read_doc(path)?;
+        let doc = mtry!(roxmltree::Document::parse(&text));
+        let root = doc.root_element();
+        if root.tag_name().name() != "map" {
+            return Err(rterr!(
+                "Expected <map> but got <{}>",
+                root.tag_name().name()
+            ));
+        }
+        if root.attribute("orientation").unwrap_or("orthogonal") != "orthogonal" {
+            return Err(rterr!("Only orthogonal maps are supported"));
+        }
+        if attr_or(root, "infinite", 0)? != 0 {
+            return Err(rterr!("Infinite maps are not supported"));
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut data = MapData {
+            width: attr_parse(root, "width")?,
+            height: attr_parse(root, "height")?,
+            tile_width: attr_parse(root, "tilewidth")?,
+            tile_height: attr_parse(root, "tileheight")?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: Vec::new(),
+        };
+        for node in root.children().filter(|c| c.is_element()) {
+            match node.tag_name().name() {
+                "tileset" => data.tilesets.push(tileset(node, dir)?),
+                "properties" => data.properties = properties(node)?,
+                _ => layer(node, [0.0, 0.0], &mut data.layers)?,
+            }
+        }
+        Ok(data)
+    }
+
+    fn tileset(node: Node, dir: &Path) -> Result<Tileset> {
+        let first_gid = attr_parse(node, "firstgid")?;
+        match node.attribute("source") {
+            Some(source) => external_tileset(&dir.join(source), first_gid),
+            None => tileset_body(node, dir, first_gid),
+        }
+    }
+
+    pub(super) fn tileset_file(path: &Path, first_gid: u32) -> Result<Tileset> {
+        let text = read_doc(path)?;
+        let doc = mtry!(roxmltree::Document::parse(&text));
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        tileset_body(doc.root_element(), dir, first_gid)
+    }
+
+    fn tileset_body(node: Node, dir: &Path, first_gid: u32) -> Result<Tileset> {
+        let name: String = attr_or(node, "name", String::new())?;
+        let image = match child(node, "image") {
+            Some(image) => dir.join(attr(image, "source")?),
+            None => {
+                return Err(rterr!(
+                    "Tileset {:?} does not use a single image, which is not supported",
+                    name
+                ))
+            }
+        };
+        let mut tiles = HashMap::new();
+        for tile in node.children().filter(|c| c.tag_name().name() == "tile") {
+            if let Some(props) = child(tile, "properties") {
+                tiles.insert(attr_parse(tile, "id")?, properties(props)?);
+            }
+        }
+        Ok(Tileset {
+            first_gid,
+            name,
+            tile_width: attr_parse(node, "tilewidth")?,
+            tile_height: attr_parse(node, "tileheight")?,
+            margin: attr_or(node, "margin", 0)?,
+            spacing: attr_or(node, "spacing", 0)?,
+            columns: attr_parse(node, "columns")?,
+            tile_count: attr_parse(node, "tilecount")?,
+            image,
+            tiles,
+        })
+    }
+
+    fn properties(node: Node) -> Result<Properties> {
+        let mut props = Vec::new();
+        for prop in node
+            .children()
+            .filter(|c| c.tag_name().name() == "property")
+        {
+            let name = attr(prop, "name")?.to_owned();
+            let type_ = prop.attribute("type").unwrap_or("string");
+            // multiline string properties are stored as text instead of an attribute
+            let value = prop.attribute("value").or(prop.text()).unwrap_or("");
+            props.push((name, parse_property(type_, value)?));
+        }
+        Ok(props)
+    }
+
+    fn layer_properties(node: Node) -> Result<Properties> {
+        match child(node, "properties") {
+            Some(props) => properties(props),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Reads the given layer node into layers, flattening groups
+    fn layer(node: Node, offset: [f32; 2], layers: &mut Vec<Layer>) -> Result<()> {
+        let offset = [
+            offset[0] + attr_or(node, "offsetx", 0.0)?,
+            offset[1] + attr_or(node, "offsety", 0.0)?,
+        ];
+        match node.tag_name().name() {
+            "layer" => layers.push(Layer::Tiles(tile_layer(node, offset)?)),
+            "objectgroup" => layers.push(Layer::Objects(object_layer(node)?)),
+            "group" => {
+                for child in node.children().filter(|c| c.is_element()) {
+                    layer(child, offset, layers)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tile_layer(node: Node, offset: [f32; 2]) -> Result<TileLayer> {
+        let data_node = match child(node, "data") {
+            Some(data) => data,
+            None => return Err(rterr!("Tile layer without <data>")),
+        };
+        let data = match data_node.attribute("encoding") {
+            Some("csv") => data_node
+                .text()
+                .unwrap_or("")
+                .split(',')
+                .map(parse_str)
+                .collect::<Result<Vec<u32>>>()?,
+            Some("base64") => base64_gids(
+                data_node.text().unwrap_or(""),
+                data_node.attribute("compression"),
+            )?,
+            Some(encoding) => return Err(rterr!("Unsupported tile encoding {:?}", encoding)),
+            None => data_node
+                .children()
+                .filter(|c| c.tag_name().name() == "tile")
+                .map(|tile| attr_or(tile, "gid", 0))
+                .collect::<Result<Vec<u32>>>()?,
+        };
+        Ok(TileLayer {
+            name: attr_or(node, "name", String::new())?,
+            width: attr_parse(node, "width")?,
+            height: attr_parse(node, "height")?,
+            data,
+            visible: attr_or(node, "visible", 1)? != 0,
+            opacity: attr_or(node, "opacity", 1.0)?,
+            offset,
+            properties: layer_properties(node)?,
+        })
+    }
+
+    fn object_layer(node: Node) -> Result<ObjectLayer> {
+        let mut objects = Vec::new();
+        for obj in node.children().filter(|c| c.tag_name().name() == "object") {
+            let mut shape = if obj.attribute("gid").is_some() {
+                "tile"
+            } else {
+                "rectangle"
+            };
+            let mut points = Vec::new();
+            for c in obj.children().filter(|c| c.is_element()) {
+                match c.tag_name().name() {
+                    "ellipse" => shape = "ellipse",
+                    "point" => shape = "point",
+                    "text" => shape = "text",
+                    name @ "polygon" | name @ "polyline" => {
+                        shape = if name == "polygon" {
+                            "polygon"
+                        } else {
+                            "polyline"
+                        };
+                        for pair in attr(c, "points")?.split_whitespace() {
+                            let mut parts = pair.split(',');
+                            let x = parse_str(parts.next().unwrap_or(""))?;
+                            let y = parse_str(parts.next().unwrap_or(""))?;
+                            points.push([x, y]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            objects.push(Object {
+                id: attr_or(obj, "id", 0)?,
+                name: attr_or(obj, "name", String::new())?,
+                type_: attr_or(obj, "type", String::new())?,
+                shape,
+                x: attr_parse(obj, "x")?,
+                y: attr_parse(obj, "y")?,
+                width: attr_or(obj, "width", 0.0)?,
+                height: attr_or(obj, "height", 0.0)?,
+                rotation: attr_or(obj, "rotation", 0.0)?,
+                gid: obj.attribute("gid").map(parse_str).transpose()?,
+                visible: attr_or(obj, "visible", 1)? != 0,
+                points,
+                properties: layer_properties(obj)?,
+            });
+        }
+        Ok(ObjectLayer {
+            name: attr_or(node, "name", String::new())?,
+            objects,
+            visible: attr_or(node, "visible", 1)? != 0,
+            properties: layer_properties(node)?,
+        })
+    }
+}
+
+mod json {
+    use super::*;
+    use serde_json::Value as Json;
+
+    fn get<'a>(json: &'a Json, key: &str) -> Result<&'a Json> {
+        match json.get(key) {
+            Some(value) => Ok(value),
+            None => Err(rterr!("Missing key {:?} in tile map", key)),
+        }
+    }
+
+    fn get_u32(json: &Json, key: &str) -> Result<u32> {
+        match get(json, key)?.as_u64() {
+            Some(x) => Ok(x as u32),
+            None => Err(rterr!("Expected integer for {:?} in tile map", key)),
+        }
+    }
+
+    fn get_u32_or(json: &Json, key: &str, default: u32) -> Result<u32> {
+        match json.get(key) {
+            Some(_) => get_u32(json, key),
+            None => Ok(default),
+        }
+    }
+
+    fn get_f32_or(json: &Json, key: &str, default: f32) -> f32 {
+        json.get(key)
+            .and_then(Json::as_f64)
+            .map(|x| x as f32)
+            .unwrap_or(default)
+    }
+
+    fn get_str_or(json: &Json, key: &str, default: &str) -> String {
+        json.get(key)
+            .and_then(Json::as_str)
+            .unwrap_or(default)
+            .to_owned()
+    }
+
+    fn get_bool_or(json: &Json, key: &str, default: bool) -> bool {
+        json.get(key).and_then(Json::as_bool).unwrap_or(default)
+    }
+
+    fn get_array<'a>(json: &'a Json, key: &str) -> Result<&'a [Json]> {
+        match json.get(key) {
+            Some(Json::Array(array)) => Ok(array),
+            None => Ok(&[]),
+            Some(_) => Err(rterr!("Expected array for {:?} in tile map", key)),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Json> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(mtry!(serde_json::from_str(&text)))
+    }
+
+    pub(super) fn map(path: &Path) -> Result<MapData> {
+        let json = read(path)?;
+        if get_str_or(&json, "orientation", "orthogonal") != "orthogonal" {
+            return Err(rterr!("Only orthogonal maps are supported"));
+        }
+        if get_bool_or(&json, "infinite", false) {
+            return Err(rterr!("Infinite maps are not supported"));
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut tilesets = Vec::new();
+        for ts in get_array(&json, "tilesets")? {
+            let first_gid = get_u32(ts, "firstgid")?;
+            tilesets.push(match ts.get("source").and_then(Json::as_str) {
+                Some(source) => external_tileset(&dir.join(source), first_gid)?,
+                None => tileset(ts, dir, first_gid)?,
+            });
+        }
+        let mut layers = Vec::new();
+        for layer_json in get_array(&json, "layers")? {
+            layer(layer_json, [0.0, 0.0], &mut layers)?;
+        }
+        Ok(MapData {
+            width: get_u32(&json, "width")?,
+            height: get_u32(&json, "height")?,
+            tile_width: get_u32(&json, "tilewidth")?,
+            tile_height: get_u32(&json, "tileheight")?,
+            tilesets,
+            layers,
+            properties: properties(&json)?,
+        })
+    }
+
+    pub(super) fn tileset_file(path: &Path, first_gid: u32) -> Result<Tileset> {
+        let json = read(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        tileset(&json, dir, first_gid)
+    }
+
+    fn tileset(json: &Json, dir: &Path, first_gid: u32) -> Result<Tileset> {
+        let name = get_str_or(json, "name", "");
+        let image = match json.get("image").and_then(Json::as_str) {
+            Some(image) => dir.join(image),
+            None => {
+                return Err(rterr!(
+                    "Tileset {:?} does not use a single image, which is not supported",
+                    name
+                ))
+            }
+        };
+        let mut tiles = HashMap::new();
+        for tile in get_array(json, "tiles")? {
+            let props = properties(tile)?;
+            if !props.is_empty() {
+                tiles.insert(get_u32(tile, "id")?, props);
+            }
+        }
+        Ok(Tileset {
+            first_gid,
+            name,
+            tile_width: get_u32(json, "tilewidth")?,
+            tile_height: get_u32(json, "tileheight")?,
+            margin: get_u32_or(json, "margin", 0)?,
+            spacing: get_u32_or(json, "spacing", 0)?,
+            columns: get_u32(json, "columns")?,
+            tile_count: get_u32(json, "tilecount")?,
+            image,
+            tiles,
+        })
+    }
+
+    fn properties(json: &Json) -> Result<Properties> {
+        let mut props = Vec::new();
+        for prop in get_array(json, "properties")? {
+            let name = get_str_or(prop, "name", "");
+            let value = match get(prop, "value")? {
+                Json::Bool(b) => Property::Bool(*b),
+                Json::Number(x) => Property::Number(x.as_f64().unwrap_or(0.0)),
+                Json::String(s) => Property::String(s.clone()),
+                value => Property::String(value.to_string()),
+            };
+            props.push((name, value));
+        }
+        Ok(props)
+    }
+
+    /// Reads the given layer into layers, flattening groups
+    fn layer(json: &Json, offset: [f32; 2], layers: &mut Vec<Layer>) -> Result<()> {
+        let offset = [
+            offset[0] + get_f32_or(json, "offsetx", 0.0),
+            offset[1] + get_f32_or(json, "offsety", 0.0),
+        ];
+        match get_str_or(json, "type", "").as_str() {
+            "tilelayer" => layers.push(Layer::Tiles(tile_layer(json, offset)?)),
+            "objectgroup" => layers.push(Layer::Objects(object_layer(json)?)),
+            "group" => {
+                for child in get_array(json, "layers")? {
+                    layer(child, offset, layers)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tile_layer(json: &Json, offset: [f32; 2]) -> Result<TileLayer> {
+        let data = match get(json, "data")? {
+            Json::String(text) => {
+                base64_gids(text, json.get("compression").and_then(Json::as_str))?
+            }
+            Json::Array(array) => array
+                .iter()
+                .map(|x| match x.as_u64() {
+                    Some(x) => Ok(x as u32),
+                    None => Err(rterr!("Expected integer tile id, but got {}", x)),
+                })
+                .collect::<Result<Vec<u32>>>()?,
+            _ => return Err(rterr!("Invalid tile layer data")),
+        };
+        Ok(TileLayer {
+            name: get_str_or(json, "name", ""),
+            width: get_u32(json, "width")?,
+            height: get_u32(json, "height")?,
+            data,
+            visible: get_bool_or(json, "visible", true),
+            opacity: get_f32_or(json, "opacity", 1.0),
+            offset,
+            properties: properties(json)?,
+        })
+    }
+
+    fn object_layer(json: &Json) -> Result<ObjectLayer> {
+        let mut objects = Vec::new();
+        for obj in get_array(json, "objects")? {
+            let gid = match obj.get("gid") {
+                Some(_) => Some(get_u32(obj, "gid")?),
+                None => None,
+            };
+            let mut shape = if gid.is_some() { "tile" } else { "rectangle" };
+            let mut points = Vec::new();
+            if get_bool_or(obj, "ellipse", false) {
+                shape = "ellipse";
+            } else if get_bool_or(obj, "point", false) {
+                shape = "point";
+            } else if obj.get("text").is_some() {
+                shape = "text";
+            }
+            for key in &["polygon", "polyline"] {
+                if obj.get(*key).is_some() {
+                    shape = *key;
+                    for point in get_array(obj, key)? {
+                        points.push([get_f32_or(point, "x", 0.0), get_f32_or(point, "y", 0.0)]);
+                    }
+                }
+            }
+            objects.push(Object {
+                id: get_u32_or(obj, "id", 0)?,
+                name: get_str_or(obj, "name", ""),
+                type_: get_str_or(obj, "type", ""),
+                shape,
+                x: get_f32_or(obj, "x", 0.0),
+                y: get_f32_or(obj, "y", 0.0),
+                width: get_f32_or(obj, "width", 0.0),
+                height: get_f32_or(obj, "height", 0.0),
+                rotation: get_f32_or(obj, "rotation", 0.0),
+                gid,
+                visible: get_bool_or(obj, "visible", true),
+                points,
+                properties: properties(obj)?,
+            });
+        }
+        Ok(ObjectLayer {
+            name: get_str_or(json, "name", ""),
+            objects,
+            visible: get_bool_or(json, "visible", true),
+            properties: properties(json)?,
+        })
+    }
+}
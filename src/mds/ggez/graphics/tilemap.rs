@@ -0,0 +1,145 @@
+use super::*;
+use crate::Handle;
+use std::path::Path;
+
+/// A loaded Tiled map: the parsed map data, and a SpriteBatch
+/// for each (tile layer, tileset) pair that has tiles
+pub struct TileMap {
+    data: MapData,
+    batches: Vec<Vec<Handle<SpriteBatch>>>,
+}
+
+impl TileMap {
+    pub fn from_file<P: AsRef<Path>>(globals: &mut Globals, path: P) -> Result<TileMap> {
+        let data = MapData::from_file(path.as_ref())?;
+        let ctx = getctx(globals)?;
+        let mut images = Vec::new();
+        for tileset in &data.tilesets {
+            let mut image = Image::from_file(ctx, &tileset.image)?;
+            image
+                .get_mut()
+                .set_filter(ggez::graphics::FilterMode::Nearest);
+            images.push(image);
+        }
+        let mut layer_batches = Vec::new();
+        for layer in &data.layers {
+            let mut batches = Vec::new();
+            if let Layer::Tiles(layer) = layer {
+                let mut params = vec![Vec::new(); images.len()];
+                for y in 0..layer.height {
+                    for x in 0..layer.width {
+                        let gid = layer.gid(x, y).unwrap();
+                        if let Some(i) = data.tileset_index(gid.gid) {
+                            let param = tile_param(&data, layer, &images[i], i, x, y, gid);
+                            params[i].push(param);
+                        }
+                    }
+                }
+                for (image, params) in images.iter().zip(params) {
+                    if params.is_empty() {
+                        continue;
+                    }
+                    let mut batch = SpriteBatch::new(
+                        ggez::graphics::spritebatch::SpriteBatch::new(image.clone().into()),
+                    );
+                    for param in params {
+                        batch.add(param);
+                    }
+                    batches.push(batch);
+                }
+            }
+            layer_batches.push(batches);
+        }
+        let mut batches = Vec::new();
+        for layer in layer_batches {
+            let mut handles = Vec::new();
+            for batch in layer {
+                handles.push(globals.new_handle(batch)?);
+            }
+            batches.push(handles);
+        }
+        Ok(TileMap { data, batches })
+    }
+    pub fn data(&self) -> &MapData {
+        &self.data
+    }
+    pub fn layer_index(&self, name: &str) -> Result<usize> {
+        match self.data.layers.iter().position(|l| l.name() == name) {
+            Some(i) => Ok(i),
+            None => Err(rterr!("Layer {:?} not found", name)),
+        }
+    }
+    pub fn tile_layer(&self, name: &str) -> Result<&TileLayer> {
+        match &self.data.layers[self.layer_index(name)?] {
+            Layer::Tiles(layer) => Ok(layer),
+            Layer::Objects(_) => Err(rterr!("{:?} is an object layer", name)),
+        }
+    }
+    pub fn object_layer(&self, name: &str) -> Result<&ObjectLayer> {
+        match &self.data.layers[self.layer_index(name)?] {
+            Layer::Objects(layer) => Ok(layer),
+            Layer::Tiles(_) => Err(rterr!("{:?} is a tile layer", name)),
+        }
+    }
+
+    /// The SpriteBatches of the given layer (one per tileset it uses)
+    pub fn batches(&self, index: usize) -> &[Handle<SpriteBatch>] {
+        &self.batches[index]
+    }
+
+    /// Returns the properties of the tile with the given gid, if it has any
+    pub fn tile_properties(&self, gid: u32) -> Option<&Properties> {
+        let gid = Gid::from(gid).gid;
+        let tileset = &self.data.tilesets[self.data.tileset_index(gid)?];
+        tileset.tiles.get(&(gid - tileset.first_gid))
+    }
+}
+
+impl ConvertValue for TileMap {}
+
+/// Computes the DrawParam for the tile at (x, y) of the given layer.
+///
+/// Tiles are drawn about their center, so that Tiled's flip flags can be
+/// expressed as a rotation and a scale. Tiles larger than the map's grid
+/// are aligned to the bottom left of their cell, as Tiled does
+fn tile_param(
+    data: &MapData,
+    layer: &TileLayer,
+    image: &Image,
+    tileset_index: usize,
+    x: u32,
+    y: u32,
+    gid: Gid,
+) -> DrawParam {
+    let tileset = &data.tilesets[tileset_index];
+    let src = tileset.tile_rect(gid.gid - tileset.first_gid);
+    let (imw, imh) = (image.get().width() as f32, image.get().height() as f32);
+    let (tw, th) = (tileset.tile_width as f32, tileset.tile_height as f32);
+    let cx = layer.offset[0] + (x * data.tile_width) as f32 + tw / 2.0;
+    let cy = layer.offset[1] + ((y + 1) * data.tile_height) as f32 - th / 2.0;
+
+    // A diagonal flip is a transpose, which is a rotation by -90 degrees
+    // following a horizontal flip. The horizontal and vertical flips are then
+    // moved past the rotation, which reverses its direction
+    let quarter = std::f32::consts::FRAC_PI_2;
+    let (rotation, xscale, yscale) = match (gid.flip_d, gid.flip_h, gid.flip_v) {
+        (false, h, v) => (0.0, if h { -1.0 } else { 1.0 }, if v { -1.0 } else { 1.0 }),
+        (true, false, false) => (-quarter, -1.0, 1.0),
+        (true, true, false) => (quarter, 1.0, 1.0),
+        (true, false, true) => (-quarter, 1.0, 1.0),
+        (true, true, true) => (quarter, -1.0, 1.0),
+    };
+
+    DrawParam::default()
+        .src(ggez::graphics::Rect::new(
+            src.x / imw,
+            src.y / imh,
+            src.w / imw,
+            src.h / imh,
+        ))
+        .dest([cx, cy])
+        .offset([0.5, 0.5])
+        .rotation(rotation)
+        .scale([xscale, yscale])
+        .color(ggez::graphics::Color::new(1.0, 1.0, 1.0, layer.opacity))
+}
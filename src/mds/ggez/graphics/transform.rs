@@ -0,0 +1,39 @@
+use super::*;
+use ggez::nalgebra as na;
+
+/// Multiplies the transform at the top of the transform stack by the given matrix
+pub fn concat_transform(ctx: &mut ggez::Context, matrix: na::Matrix4<f32>) -> Result<()> {
+    let current: na::Matrix4<f32> = ggez::graphics::transform(ctx).into();
+    set_transform(ctx, current * matrix)
+}
+
+/// Replaces the transform at the top of the transform stack
+pub fn set_transform(ctx: &mut ggez::Context, matrix: na::Matrix4<f32>) -> Result<()> {
+    ggez::graphics::set_transform(ctx, matrix.into());
+    mtry!(ggez::graphics::apply_transformations(ctx));
+    Ok(())
+}
+
+pub fn push_transform(ctx: &mut ggez::Context) -> Result<()> {
+    ggez::graphics::push_transform(ctx, None);
+    mtry!(ggez::graphics::apply_transformations(ctx));
+    Ok(())
+}
+
+pub fn pop_transform(ctx: &mut ggez::Context) -> Result<()> {
+    ggez::graphics::pop_transform(ctx);
+    mtry!(ggez::graphics::apply_transformations(ctx));
+    Ok(())
+}
+
+pub fn translation(x: f32, y: f32) -> na::Matrix4<f32> {
+    na::Matrix4::new_translation(&na::Vector3::new(x, y, 0.0))
+}
+
+pub fn rotation(angle: f32) -> na::Matrix4<f32> {
+    na::Matrix4::new_rotation(na::Vector3::new(0.0, 0.0, angle))
+}
+
+pub fn scaling(x: f32, y: f32) -> na::Matrix4<f32> {
+    na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(x, y, 1.0))
+}
@@ -0,0 +1,564 @@
+//! Running the event loop without a human at the keyboard, e.g. in tests
+//!
+//! The callbacks given to a.ggez.run are called for a fixed number of frames,
+//! with fixed deltas, and with input events taken from a script instead of
+//! the window. Without rendering, no ggez context (and so no window or display)
+//! is needed at all, and drawing calls do nothing.
+//!
+//! a.ggez.timer follows the scripted deltas rather than the wall clock, and
+//! a.ggez.input reports the keys, mouse and gamepads as left by the scripted
+//! events rather than the window, with or without rendering.
+use super::*;
+use ggez::event::KeyMods;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+pub const NAME: &str = "a.ggez.headless";
+
+pub(super) fn new() -> NativeModule {
+    NativeModule::new(NAME, |m| {
+        m.func(
+            "is_headless",
+            [],
+            "Checks whether the event loop is running headless",
+            |globals, _, _| Ok(globals.stash().has::<HeadlessState>().into()),
+        );
+        m.func(
+            "frame",
+            [],
+            "Returns the number of the current headless frame, starting from 0",
+            |globals, _, _| {
+                let stash = globals.stash();
+                if !stash.has::<HeadlessState>() {
+                    return Err(rterr!("Not running headless"));
+                }
+                Ok(stash.get::<HeadlessState>()?.frame.into())
+            },
+        );
+        m.func(
+            "load_recording",
+            ["path"],
+            concat!(
+                "Loads a recording made with the 'record' option of a.ggez.run, ",
+                "and returns it as a map of headless options, with 'frames', ",
+                "'dt' (the list of recorded deltas) and 'events'",
+            ),
+            |_globals, args, _| {
+                let mut args = args.into_iter();
+                let path = args.next().unwrap().into_string()?;
+                Ok(Recording::load(path.str())?.into())
+            },
+        );
+    })
+}
+
+const DEFAULT_SIZE: [f32; 2] = [800.0, 600.0];
+
+/// The kinds of scripted events, with their minimum and maximum
+/// number of arguments
+const EVENT_KINDS: &[(&str, usize, usize)] = &[
+    ("key_down", 1, 3),
+    ("key_up", 1, 2),
+    ("text_input", 1, 1),
+    ("mouse_down", 2, 3),
+    ("mouse_up", 2, 3),
+    ("mouse_move", 2, 4),
+    ("mouse_wheel", 2, 2),
+    ("resize", 2, 2),
+    ("gamepad_down", 2, 2),
+    ("gamepad_up", 2, 2),
+    ("gamepad_axis", 3, 3),
+    ("quit", 0, 0),
+];
+
+pub struct ScriptedEvent {
+    frame: usize,
+    kind: RcStr,
+    args: Vec<Value>,
+}
+
+impl TryFrom<Value> for ScriptedEvent {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        let list = value.into_list()?;
+        let list: Vec<Value> = list.borrow().iter().cloned().collect();
+        let mut parts = list.into_iter();
+        let frame = match parts.next() {
+            Some(frame) => frame.usize()?,
+            None => return Err(rterr!("Scripted events must start with a frame number")),
+        };
+        let kind = match parts.next() {
+            Some(kind) => kind.into_string()?,
+            None => return Err(rterr!("Scripted event at frame {} has no kind", frame)),
+        };
+        let args: Vec<Value> = parts.collect();
+        match EVENT_KINDS.iter().find(|(name, _, _)| *name == kind.str()) {
+            Some((_, min, max)) if args.len() < *min || args.len() > *max => Err(rterr!(
+                "Scripted {:?} event at frame {} expects {} to {} arguments, but got {}",
+                kind,
+                frame,
+                min,
+                max,
+                args.len()
+            )),
+            Some(_) => Ok(Self { frame, kind, args }),
+            None => Err(rterr!("Unrecognized scripted event kind {:?}", kind)),
+        }
+    }
+}
+
+/// Headless options, given as a map (see the docs of a.ggez.run)
+pub struct Headless {
+    pub frames: usize,
+    /// The delta of each frame, or a single delta used for all of them
+    pub dt: Vec<f64>,
+    pub events: Vec<ScriptedEvent>,
+    pub render: bool,
+    pub capture: Option<PathBuf>,
+    pub capture_every: usize,
+    pub size: [f32; 2],
+}
+
+impl TryFrom<Value> for Headless {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        Self::parse(value, None)
+    }
+}
+
+impl Headless {
+    /// Headless options for replaying a recording (see the 'record' option of a.ggez.run).
+    /// The options may be nil, and must not contain 'frames', 'dt' or 'events',
+    /// which come from the recording. Unlike for plain headless runs,
+    /// 'render' defaults to true
+    pub fn replay(recording: Recording, options: Value) -> Result<Self> {
+        Self::parse(options, Some(recording))
+    }
+
+    fn parse(value: Value, recording: Option<Recording>) -> Result<Self> {
+        let replaying = recording.is_some();
+        let mut map = match value {
+            Value::Map(map) => map.to_string_keys()?,
+            Value::Nil if replaying => Default::default(),
+            value => return Err(rterr!("Expected headless options map, but got {:?}", value)),
+        };
+        let (frames, dt, events) = match recording {
+            Some(recording) => {
+                for key in &["frames", "dt", "events"] {
+                    if map.remove(*key).is_some() {
+                        return Err(rterr!(
+                            "Headless option {:?} cannot be used when replaying",
+                            key
+                        ));
+                    }
+                }
+                let frames = recording.dts.len();
+                (frames, recording.dts, recording.events)
+            }
+            None => {
+                let frames = match map.remove("frames") {
+                    Some(frames) => frames.usize()?,
+                    None => return Err(rterr!("Headless options require 'frames'")),
+                };
+                let dt = match map.remove("dt") {
+                    Some(Value::List(list)) => {
+                        let dts: Vec<Value> = list.borrow().iter().cloned().collect();
+                        let dts = dts
+                            .into_iter()
+                            .map(|dt| dt.f64())
+                            .collect::<Result<Vec<_>>>()?;
+                        if dts.len() < frames {
+                            return Err(rterr!(
+                                "Got {} deltas for {} headless frames",
+                                dts.len(),
+                                frames
+                            ));
+                        }
+                        dts
+                    }
+                    Some(dt) => vec![dt.f64()?],
+                    None => vec![1.0 / 60.0],
+                };
+                let events = match map.remove("events") {
+                    Some(events) => {
+                        let events = events.into_list()?;
+                        let events: Vec<Value> = events.borrow().iter().cloned().collect();
+                        events
+                    }
+                    None => vec![],
+                };
+                (frames, dt, events)
+            }
+        };
+        let mut events = events
+            .into_iter()
+            .map(ScriptedEvent::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        // stable, so events in the same frame keep their order
+        events.sort_by_key(|event| event.frame);
+        let render = map
+            .remove("render")
+            .map(|v| v.truthy())
+            .unwrap_or(replaying);
+        let capture = match map.remove("capture") {
+            Some(Value::Nil) | None => None,
+            Some(dir) => Some(PathBuf::from(dir.into_string()?.str())),
+        };
+        if capture.is_some() && !render {
+            return Err(rterr!("Headless 'capture' requires 'render'"));
+        }
+        let capture_every = match map.remove("capture_every") {
+            Some(n) => n.usize()?.max(1),
+            None => 1,
+        };
+        let size = match map.remove("size") {
+            Some(size) => <[f32; 2]>::try_from(size)?,
+            None => DEFAULT_SIZE,
+        };
+        if !map.is_empty() {
+            let keys: Vec<_> = map.keys().collect();
+            return Err(rterr!("Unused headless options: {:?}", keys));
+        }
+        Ok(Self {
+            frames,
+            dt,
+            events,
+            render,
+            capture,
+            capture_every,
+            size,
+        })
+    }
+}
+
+impl Headless {
+    fn dt(&self, frame: usize) -> f64 {
+        if self.dt.len() == 1 {
+            self.dt[0]
+        } else {
+            self.dt[frame]
+        }
+    }
+}
+
+/// Stands in for the ggez context when running headless without rendering
+pub(super) struct Stub {
+    pub(super) size: [f32; 2],
+    pub(super) paused: bool,
+    pub(super) quit: bool,
+}
+
+/// The scripted clock and input of a headless run, read by a.ggez.timer
+/// and a.ggez.input in place of the wall clock and the window, so that
+/// runs stay deterministic
+pub(super) struct HeadlessState {
+    pub(super) frame: usize,
+    pub(super) ticks: usize,
+    pub(super) delta: f64,
+    pub(super) elapsed: f64,
+    pub(super) residual: f64,
+    recent: VecDeque<f64>,
+
+    pub(super) keys: BTreeSet<String>,
+    pub(super) mods: KeyMods,
+    pub(super) key_repeated: bool,
+    pub(super) mouse_position: [f64; 2],
+    pub(super) mouse_buttons: BTreeSet<String>,
+    pub(super) gamepads: BTreeSet<usize>,
+    pub(super) gamepad_buttons: BTreeSet<(usize, String)>,
+    pub(super) gamepad_axes: BTreeMap<(usize, String), f64>,
+}
+
+/// Same as the number of frames ggez averages over
+const AVERAGE_FRAMES: usize = 200;
+
+impl HeadlessState {
+    fn new() -> Self {
+        Self {
+            frame: 0,
+            ticks: 0,
+            delta: 0.0,
+            elapsed: 0.0,
+            residual: 0.0,
+            recent: VecDeque::new(),
+            keys: BTreeSet::new(),
+            mods: KeyMods::empty(),
+            key_repeated: false,
+            mouse_position: [0.0, 0.0],
+            mouse_buttons: BTreeSet::new(),
+            gamepads: BTreeSet::new(),
+            gamepad_buttons: BTreeSet::new(),
+            gamepad_axes: BTreeMap::new(),
+        }
+    }
+    fn tick(&mut self, frame: usize, dt: f64) {
+        self.frame = frame;
+        self.ticks += 1;
+        self.delta = dt;
+        self.elapsed += dt;
+        self.residual += dt;
+        if self.recent.len() == AVERAGE_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(dt);
+    }
+    pub(super) fn average_delta(&self) -> f64 {
+        if self.recent.is_empty() {
+            0.0
+        } else {
+            self.recent.iter().sum::<f64>() / self.recent.len() as f64
+        }
+    }
+}
+
+/// Runs f on the scripted clock and input, if running headless
+pub(super) fn with_headless_state<R, F: FnOnce(&mut HeadlessState) -> R>(
+    globals: &mut Globals,
+    f: F,
+) -> Option<R> {
+    let stash = globals.stash_mut();
+    if stash.has::<HeadlessState>() {
+        Some(f(stash.get_mut::<HeadlessState>().unwrap()))
+    } else {
+        None
+    }
+}
+
+/// If a.ggez.init was already called, its context stays available for
+/// e.g. loading images, but drawing calls still do nothing
+pub(super) fn init_stub(globals: &mut Globals, headless: &Headless) -> Result<()> {
+    globals.stash_mut().set(Stub {
+        size: headless.size,
+        paused: false,
+        quit: false,
+    })?;
+    Ok(())
+}
+
+pub(super) fn is_stubbed(globals: &Globals) -> bool {
+    globals.stash().has::<Stub>()
+}
+
+/// The screen size when running headless without rendering
+pub(super) fn headless_size(globals: &Globals) -> [f32; 2] {
+    let stash = globals.stash();
+    if stash.has::<Stub>() {
+        stash.get::<Stub>().unwrap().size
+    } else {
+        DEFAULT_SIZE
+    }
+}
+
+pub(super) fn set_stub_quit(globals: &mut Globals) {
+    let stash = globals.stash_mut();
+    if stash.has::<Stub>() {
+        stash.get_mut::<Stub>().unwrap().quit = true;
+    }
+}
+
+fn should_quit(handler: &mut EventHandler) -> bool {
+    if handler.quit {
+        return true;
+    }
+    match drawctx(&mut handler.globals) {
+        Ok(Some(ctx)) => !ctx.continuing,
+        Ok(None) => {
+            let stash = handler.globals.stash();
+            stash.has::<Stub>() && stash.get::<Stub>().unwrap().quit
+        }
+        Err(_) => true,
+    }
+}
+
+pub(super) fn run_headless(handler: &mut EventHandler, mut headless: Headless) {
+    let r = handler.globals.stash_mut().set(HeadlessState::new());
+    handler.check(r);
+    let mut events = std::mem::take(&mut headless.events).into_iter().peekable();
+    'frames: for frame in 0..headless.frames {
+        let dt = headless.dt(frame);
+        with_headless_state(&mut handler.globals, |state| state.tick(frame, dt));
+        while let Some(event) = events.peek() {
+            if event.frame > frame {
+                break;
+            }
+            let event = events.next().unwrap();
+            handler.record(event.kind.str(), &event.args);
+            let r = handler.dispatch_scripted(event);
+            handler.check(r);
+            if should_quit(handler) {
+                break 'frames;
+            }
+        }
+        handler.record_update(dt);
+        handler.dispatch_update(dt);
+        if should_quit(handler) {
+            break;
+        }
+        if headless.render {
+            let r = render_frame(handler, frame, &headless.capture, headless.capture_every);
+            handler.check(r);
+        } else {
+            handler.call(handler.draw.clone(), vec![]);
+        }
+        if should_quit(handler) {
+            break;
+        }
+    }
+    handler.globals.stash_mut().remove::<HeadlessState>();
+}
+
+fn render_frame(
+    handler: &mut EventHandler,
+    frame: usize,
+    capture: &Option<PathBuf>,
+    capture_every: usize,
+) -> Result<()> {
+    {
+        // Window events still need to be processed for the window to
+        // render properly, but all input comes from the script
+        let ctx = getctx(&mut handler.globals)?;
+        let mut stash = handler.globals.stash_mut().get_mut::<Stash>()?;
+        if let Some(event_loop) = &mut stash.event_loop {
+            event_loop.poll_events(|event| {
+                ctx.process_event(&event);
+            });
+        }
+    }
+    handler.call(handler.draw.clone(), vec![]);
+    let ctx = getctx(&mut handler.globals)?;
+    mtry!(ggez::graphics::present(ctx));
+    if let Some(dir) = capture {
+        if frame % capture_every == 0 {
+            let image = graphics::Image::from(mtry!(ggez::graphics::screenshot(ctx)));
+            let bytes = image.encode(ctx, "png")?;
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(dir.join(format!("frame{:05}.png", frame)), bytes)?;
+        }
+    }
+    Ok(())
+}
+
+impl EventHandler {
+    fn dispatch_scripted(&mut self, event: ScriptedEvent) -> Result<()> {
+        let mut args = event.args.into_iter();
+        let mut next = |default: Value| args.next().unwrap_or(default);
+        let no_mods = || input::keymods_to_value(ggez::event::KeyMods::empty());
+        match event.kind.str() {
+            "key_down" => {
+                let key = next(Value::Nil);
+                let mods = next(no_mods());
+                let repeat = next(false.into()).truthy();
+                let keymods = input::keymods_from_value(mods.clone())?;
+                let name = key.clone().into_string()?;
+                with_headless_state(&mut self.globals, |state| {
+                    state.keys.insert(name.str().to_owned());
+                    state.mods = keymods;
+                    state.key_repeated = repeat;
+                });
+                self.dispatch_key_down(key, repeat, mods);
+            }
+            "key_up" => {
+                let key = next(Value::Nil);
+                let mods = next(no_mods());
+                let keymods = input::keymods_from_value(mods.clone())?;
+                let name = key.clone().into_string()?;
+                with_headless_state(&mut self.globals, |state| {
+                    state.keys.remove(name.str());
+                    state.mods = keymods;
+                });
+                self.call(self.key_up.clone(), vec![key, mods]);
+            }
+            "text_input" => {
+                let ch = next(Value::Nil);
+                self.call(self.text_input.clone(), vec![ch]);
+            }
+            kind @ "mouse_down" | kind @ "mouse_up" => {
+                let x = next(Value::Nil).f64()?;
+                let y = next(Value::Nil).f64()?;
+                let button = next("Left".into());
+                let name = input::mouse_button_name(&button)?;
+                let down = kind == "mouse_down";
+                with_headless_state(&mut self.globals, |state| {
+                    state.mouse_position = [x, y];
+                    if down {
+                        state.mouse_buttons.insert(name);
+                    } else {
+                        state.mouse_buttons.remove(&name);
+                    }
+                });
+                let callback = if down {
+                    self.mouse_down.clone()
+                } else {
+                    self.mouse_up.clone()
+                };
+                self.call(callback, vec![x.into(), y.into(), button]);
+            }
+            "mouse_move" => {
+                let x = next(Value::Nil).f64()?;
+                let y = next(Value::Nil).f64()?;
+                let dx = Value::from(next(Value::from(0.0)).f64()?);
+                let dy = Value::from(next(Value::from(0.0)).f64()?);
+                with_headless_state(&mut self.globals, |state| {
+                    state.mouse_position = [x, y];
+                });
+                self.call(self.mouse_move.clone(), vec![x.into(), y.into(), dx, dy]);
+            }
+            "mouse_wheel" => {
+                let x = Value::from(next(Value::Nil).f64()?);
+                let y = Value::from(next(Value::Nil).f64()?);
+                self.call(self.mouse_wheel.clone(), vec![x, y]);
+            }
+            "resize" => {
+                let width = next(Value::Nil).f32()?;
+                let height = next(Value::Nil).f32()?;
+                let stash = self.globals.stash_mut();
+                if stash.has::<Stub>() {
+                    stash.get_mut::<Stub>()?.size = [width, height];
+                }
+                let args = vec![(width as f64).into(), (height as f64).into()];
+                self.call(self.resize.clone(), args);
+            }
+            kind @ "gamepad_down" | kind @ "gamepad_up" => {
+                let id = next(Value::Nil).usize()?;
+                let button = next(Value::Nil).into_string()?;
+                let down = kind == "gamepad_down";
+                let key = (id, button.str().to_owned());
+                with_headless_state(&mut self.globals, |state| {
+                    state.gamepads.insert(id);
+                    if down {
+                        state.gamepad_buttons.insert(key);
+                    } else {
+                        state.gamepad_buttons.remove(&key);
+                    }
+                });
+                let callback = if down {
+                    self.gamepad_down.clone()
+                } else {
+                    self.gamepad_up.clone()
+                };
+                self.call(callback, vec![id.into(), button.into()]);
+            }
+            "gamepad_axis" => {
+                let id = next(Value::Nil).usize()?;
+                let axis = next(Value::Nil).into_string()?;
+                let value = next(Value::Nil).f64()?;
+                let key = (id, axis.str().to_owned());
+                with_headless_state(&mut self.globals, |state| {
+                    state.gamepads.insert(id);
+                    state.gamepad_axes.insert(key, value);
+                });
+                let args = vec![id.into(), axis.into(), value.into()];
+                self.call(self.gamepad_axis.clone(), args);
+            }
+            "quit" => {
+                if self.dispatch_quit() {
+                    self.quit = true;
+                }
+            }
+            kind => return Err(rterr!("Unrecognized scripted event kind {:?}", kind)),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! Polling the keyboard, mouse and gamepads. When running headless, these
+//! report the state left by the scripted events instead of the window
+use super::*;
+use crate::Key;
+use crate::Map;
+use ggez::event::Axis;
+use ggez::event::Button;
+use ggez::event::KeyMods;
+use ggez::event::MouseButton;
+
+pub const NAME: &str = "a.ggez.input";
+
+pub(super) fn new() -> NativeModule {
+    NativeModule::new(NAME, |m| {
+        m.func(
+            "is_key_pressed",
+            ["key"],
+            "Checks whether the key with the given name is currently held down",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let key = args.next().unwrap().into_string()?;
+                if let Some(pressed) = with_headless_state(globals, |s| s.keys.contains(key.str()))
+                {
+                    return Ok(pressed.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::input::keyboard::pressed_keys(ctx)
+                    .iter()
+                    .any(|keycode| format!("{:?}", keycode) == key.str())
+                    .into())
+            },
+        );
+        m.func(
+            "pressed_keys",
+            [],
+            "Returns the names of all keys currently held down",
+            |globals, _, _| {
+                let headless =
+                    with_headless_state(globals, |s| s.keys.iter().cloned().collect::<Vec<_>>());
+                let mut keys: Vec<String> = match headless {
+                    Some(keys) => keys,
+                    None => ggez::input::keyboard::pressed_keys(getctx(globals)?)
+                        .iter()
+                        .map(|keycode| format!("{:?}", keycode))
+                        .collect(),
+                };
+                keys.sort();
+                Ok(keys.into_iter().map(Value::from).collect::<Vec<_>>().into())
+            },
+        );
+        m.func(
+            "active_mods",
+            [],
+            concat!(
+                "Returns the currently active modifiers, as a map with ",
+                "'shift', 'ctrl', 'alt' and 'logo' keys",
+            ),
+            |globals, _, _| {
+                if let Some(mods) = with_headless_state(globals, |s| s.mods) {
+                    return Ok(keymods_to_value(mods));
+                }
+                let ctx = getctx(globals)?;
+                Ok(keymods_to_value(ggez::input::keyboard::active_mods(ctx)))
+            },
+        );
+        m.func(
+            "is_key_repeated",
+            [],
+            "Checks whether the last key event was a repeat",
+            |globals, _, _| {
+                if let Some(repeated) = with_headless_state(globals, |s| s.key_repeated) {
+                    return Ok(repeated.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::input::keyboard::is_key_repeated(ctx).into())
+            },
+        );
+        m.func(
+            "mouse_position",
+            [],
+            "Returns the current [x, y] position of the mouse",
+            |globals, _, _| {
+                let [x, y] = match with_headless_state(globals, |s| s.mouse_position) {
+                    Some(position) => position,
+                    None => {
+                        let p = ggez::input::mouse::position(getctx(globals)?);
+                        [p.x as f64, p.y as f64]
+                    }
+                };
+                Ok(vec![Value::from(x), Value::from(y)].into())
+            },
+        );
+        m.func(
+            "is_mouse_button_pressed",
+            ["button"],
+            concat!(
+                "Checks whether the given mouse button ('Left', 'Right', 'Middle', ",
+                "or the number of any other button) is currently held down",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let button = args.next().unwrap();
+                let name = mouse_button_name(&button)?;
+                if let Some(pressed) =
+                    with_headless_state(globals, |s| s.mouse_buttons.contains(&name))
+                {
+                    return Ok(pressed.into());
+                }
+                let button = parse_mouse_button(&button)?;
+                let ctx = getctx(globals)?;
+                Ok(ggez::input::mouse::button_pressed(ctx, button).into())
+            },
+        );
+        m.func(
+            "gamepads",
+            [],
+            "Returns the ids of all connected gamepads",
+            |globals, _, _| {
+                let headless = with_headless_state(globals, |s| s.gamepads.clone());
+                if let Some(ids) = headless {
+                    return Ok(ids.into_iter().map(Value::from).collect::<Vec<_>>().into());
+                }
+                let ctx = getctx(globals)?;
+                let ids: Vec<Value> = ggez::input::gamepad::gamepads(ctx)
+                    .filter(|(_, gamepad)| gamepad.is_connected())
+                    .map(|(_, gamepad)| {
+                        let id: usize = gamepad.id().into();
+                        Value::from(id)
+                    })
+                    .collect();
+                Ok(ids.into())
+            },
+        );
+        m.func("gamepad_name", ["gamepad_id"], "", |globals, args, _| {
+            let mut args = args.into_iter();
+            let id = args.next().unwrap().usize()?;
+            if let Some(known) = with_headless_state(globals, |s| s.gamepads.contains(&id)) {
+                return if known {
+                    Ok(format!("Headless gamepad {}", id).into())
+                } else {
+                    Err(rterr!("Gamepad {} not found", id))
+                };
+            }
+            let ctx = getctx(globals)?;
+            let id = find_gamepad(ctx, id)?;
+            Ok(ggez::input::gamepad::gamepad(ctx, id).name().into())
+        });
+        m.func(
+            "is_gamepad_button_pressed",
+            ["gamepad_id", "button"],
+            "",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let id = args.next().unwrap().usize()?;
+                let button = parse_button(args.next().unwrap())?;
+                let key = (id, format!("{:?}", button));
+                if let Some(pressed) =
+                    with_headless_state(globals, |s| s.gamepad_buttons.contains(&key))
+                {
+                    return Ok(pressed.into());
+                }
+                let ctx = getctx(globals)?;
+                let id = find_gamepad(ctx, id)?;
+                Ok(ggez::input::gamepad::gamepad(ctx, id)
+                    .is_pressed(button)
+                    .into())
+            },
+        );
+        m.func(
+            "gamepad_axis",
+            ["gamepad_id", "axis"],
+            "Returns the current value of the given axis, in the range [-1, 1]",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let id = args.next().unwrap().usize()?;
+                let axis = parse_axis(args.next().unwrap())?;
+                let key = (id, format!("{:?}", axis));
+                let headless = with_headless_state(globals, |s| s.gamepad_axes.get(&key).cloned());
+                if let Some(value) = headless {
+                    return Ok(value.unwrap_or(0.0).into());
+                }
+                let ctx = getctx(globals)?;
+                let id = find_gamepad(ctx, id)?;
+                Ok((ggez::input::gamepad::gamepad(ctx, id).value(axis) as f64).into())
+            },
+        );
+    })
+}
+
+pub(super) fn keymods_to_value(mods: KeyMods) -> Value {
+    vec![
+        ("shift", mods.contains(KeyMods::SHIFT)),
+        ("ctrl", mods.contains(KeyMods::CTRL)),
+        ("alt", mods.contains(KeyMods::ALT)),
+        ("logo", mods.contains(KeyMods::LOGO)),
+    ]
+    .into_iter()
+    .map(|(k, v)| (Key::from(k.to_owned()), Value::from(v)))
+    .collect::<Map>()
+    .into()
+}
+
+/// Parses a map of the form returned by keymods_to_value
+/// (missing keys count as false)
+pub(super) fn keymods_from_value(value: Value) -> Result<KeyMods> {
+    let mut map = match value {
+        Value::Map(map) => map.to_string_keys()?,
+        value => return Err(rterr!("Expected key mods map, but got {:?}", value)),
+    };
+    let mut mods = KeyMods::empty();
+    for (name, flag) in &[
+        ("shift", KeyMods::SHIFT),
+        ("ctrl", KeyMods::CTRL),
+        ("alt", KeyMods::ALT),
+        ("logo", KeyMods::LOGO),
+    ] {
+        if map.remove(*name).map(|v| v.truthy()).unwrap_or(false) {
+            mods |= *flag;
+        }
+    }
+    Ok(mods)
+}
+
+/// The name of a mouse button as passed to the mouse callbacks:
+/// 'Left', 'Right', 'Middle', or the number of any other button
+pub(super) fn mouse_button_name(value: &Value) -> Result<String> {
+    match value {
+        Value::String(name) => Ok(name.str().to_owned()),
+        Value::Number(_) => Ok(format!("{}", value.usize()?)),
+        value => Err(rterr!("Expected mouse button, but got {:?}", value)),
+    }
+}
+
+fn parse_mouse_button(value: &Value) -> Result<MouseButton> {
+    match mouse_button_name(value)?.as_str() {
+        "Left" => Ok(MouseButton::Left),
+        "Right" => Ok(MouseButton::Right),
+        "Middle" => Ok(MouseButton::Middle),
+        name => match name.parse::<u8>() {
+            Ok(x) => Ok(MouseButton::Other(x)),
+            Err(_) => Err(rterr!("Unrecognized mouse button {:?}", name)),
+        },
+    }
+}
+
+const BUTTONS: &[Button] = &[
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+const AXES: &[Axis] = &[
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+];
+
+fn parse_button(value: Value) -> Result<Button> {
+    let name = value.into_string()?;
+    for button in BUTTONS {
+        if format!("{:?}", button) == name.str() {
+            return Ok(*button);
+        }
+    }
+    Err(rterr!("Unrecognized gamepad button {:?}", name))
+}
+
+fn parse_axis(value: Value) -> Result<Axis> {
+    let name = value.into_string()?;
+    for axis in AXES {
+        if format!("{:?}", axis) == name.str() {
+            return Ok(*axis);
+        }
+    }
+    Err(rterr!("Unrecognized gamepad axis {:?}", name))
+}
+
+fn find_gamepad(ctx: &ggez::Context, id: usize) -> Result<ggez::input::gamepad::GamepadId> {
+    for (gid, gamepad) in ggez::input::gamepad::gamepads(ctx) {
+        let gamepad_id: usize = gamepad.id().into();
+        if gamepad_id == id {
+            return Ok(gid);
+        }
+    }
+    Err(rterr!("Gamepad {} not found", id))
+}
+
+pub(super) fn gamepad_id_to_value(
+    ctx: &ggez::Context,
+    id: ggez::input::gamepad::GamepadId,
+) -> Value {
+    let id: usize = ggez::input::gamepad::gamepad(ctx, id).id().into();
+    Value::from(id)
+}
@@ -13,12 +13,24 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 
 pub mod audio;
+pub mod conf;
 pub mod graphics;
+pub mod headless;
+pub mod input;
+pub mod record;
+pub mod timer;
+
+use conf::*;
+use headless::*;
+use record::*;
 
 pub(super) fn add(globals: &mut Globals) {
     globals.add_native_module(new()).unwrap();
     globals.add_native_module(graphics::new()).unwrap();
+    globals.add_native_module(headless::new()).unwrap();
     globals.add_native_module(audio::new()).unwrap();
+    globals.add_native_module(input::new()).unwrap();
+    globals.add_native_module(timer::new()).unwrap();
 }
 
 pub const NAME: &str = "a.ggez";
@@ -35,12 +47,159 @@ struct EventHandler {
     key_up: Option<Value>,
     text_input: Option<Value>,
     resize: Option<Value>,
+    gamepad_down: Option<Value>,
+    gamepad_up: Option<Value>,
+    gamepad_axis: Option<Value>,
+    quit_requested: Option<Value>,
+    on_error: Option<Value>,
+    key_repeat: bool,
+    quit_on_escape: bool,
+    quit: bool,
+    /// The unhandled error that stopped the event loop, if any
+    error: Option<Error>,
+    recorder: Option<Recorder>,
 
     keycode_map: HashMap<ggez::event::KeyCode, RcStr>,
     mouse_button_map: HashMap<ggez::event::MouseButton, RcStr>,
+    gamepad_button_map: HashMap<ggez::event::Button, RcStr>,
+    gamepad_axis_map: HashMap<ggez::event::Axis, RcStr>,
+}
+
+/// An error raised by a callback, as passed to on_error
+pub struct CallbackError {
+    message: String,
+    trace: Vec<String>,
+    description: String,
+}
+
+impl CallbackError {
+    fn new(error: &Error) -> Self {
+        Self {
+            message: error.message().to_string(),
+            trace: error
+                .trace()
+                .iter()
+                .map(|mark| format!("{:?}", mark))
+                .collect(),
+            description: format!("{:?}", error),
+        }
+    }
 }
 
 impl EventHandler {
+    /// Calls the given callback (if any), letting 'on_error' handle any error
+    fn call(&mut self, callback: Option<Value>, args: Vec<Value>) -> Option<Value> {
+        let callback = callback?;
+        let r = callback.apply(&mut self.globals, args, None);
+        self.check(r)
+    }
+    /// Unwraps the result of a callback, or if it is an error,
+    /// lets the 'on_error' callback decide what to do with it
+    fn check<T>(&mut self, r: Result<T>) -> Option<T> {
+        match r {
+            Ok(t) => Some(t),
+            Err(error) => {
+                self.handle_error(error);
+                None
+            }
+        }
+    }
+    fn handle_error(&mut self, error: Error) {
+        let on_error = match self.on_error.clone() {
+            Some(on_error) => on_error,
+            None => {
+                self.fail(error);
+                return;
+            }
+        };
+        let handle = match self.globals.new_handle(CallbackError::new(&error)) {
+            Ok(handle) => handle,
+            Err(_) => {
+                self.fail(error);
+                return;
+            }
+        };
+        let action = match on_error.apply(&mut self.globals, vec![handle.into()], None) {
+            Ok(action) => action,
+            Err(on_error_error) => {
+                eprintln!("Error in on_error handler: {:?}", on_error_error);
+                self.fail(error);
+                return;
+            }
+        };
+        match action {
+            Value::Nil => {}
+            Value::String(string) if string.str() == "continue" => {}
+            Value::String(string) if string.str() == "pause" => {
+                set_paused(&mut self.globals, true);
+            }
+            Value::String(string) if string.str() == "quit" => {
+                self.quit = true;
+            }
+            action => {
+                eprintln!(
+                    "on_error must return 'continue', 'pause', 'quit' or nil, but got {:?}",
+                    action
+                );
+                self.fail(error);
+            }
+        }
+    }
+    /// Stops the event loop because of an unhandled error, which is
+    /// reported once ggez has been shut down (see shutdown)
+    fn fail(&mut self, error: Error) {
+        self.quit = true;
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+    /// Shuts ggez down after the event loop has exited, and then reports
+    /// the error that stopped it (if any) with its mtots stack trace,
+    /// the same way as errors outside of callbacks
+    fn shutdown(mut self) {
+        self.finish_recording();
+        deinitggez(&mut self.globals);
+        if let Some(error) = self.error.take() {
+            ordie::<()>(&mut self.globals, Err(error));
+        }
+    }
+    /// Forwards a quit requested by a callback to the ggez event loop
+    fn sync_quit(&self, ctx: &mut ggez::Context) {
+        if self.quit {
+            ggez::event::quit(ctx);
+        }
+    }
+    fn dispatch_update(&mut self, dt: f64) {
+        audio::step_fades(&mut self.globals, dt);
+        if !is_paused(&self.globals) {
+            self.call(self.update.clone(), vec![dt.into()]);
+        }
+    }
+    fn dispatch_key_down(&mut self, key: Value, repeat: bool, mods: Value) {
+        if self.quit_on_escape {
+            if let Value::String(string) = &key {
+                if string.str() == "Escape" {
+                    // Same as closing the window, so quit_requested can cancel it
+                    if self.dispatch_quit() {
+                        self.quit = true;
+                    }
+                    return;
+                }
+            }
+        }
+        if repeat && !self.key_repeat {
+            return;
+        }
+        self.call(self.key_down.clone(), vec![key, repeat.into(), mods]);
+    }
+    /// Asks 'quit_requested' whether to quit, and returns true if we should
+    fn dispatch_quit(&mut self) -> bool {
+        // Only an explicit 'false' cancels the quit
+        !matches!(
+            self.call(self.quit_requested.clone(), vec![]),
+            Some(Value::Bool(false))
+        )
+    }
     fn translate_keycode(&mut self, keycode: ggez::event::KeyCode) -> Value {
         match self.keycode_map.entry(keycode) {
             std::collections::hash_map::Entry::Vacant(entry) => (*entry
@@ -63,22 +222,40 @@ impl EventHandler {
             }
         }
     }
+    fn translate_gamepad_button(&mut self, btn: ggez::event::Button) -> Value {
+        match self.gamepad_button_map.entry(btn) {
+            std::collections::hash_map::Entry::Vacant(entry) => (*entry
+                .insert(RcStr::from(format!("{:?}", btn))))
+            .clone()
+            .into(),
+            std::collections::hash_map::Entry::Occupied(entry) => (*entry.get()).clone().into(),
+        }
+    }
+    fn translate_gamepad_axis(&mut self, axis: ggez::event::Axis) -> Value {
+        match self.gamepad_axis_map.entry(axis) {
+            std::collections::hash_map::Entry::Vacant(entry) => (*entry
+                .insert(RcStr::from(format!("{:?}", axis))))
+            .clone()
+            .into(),
+            std::collections::hash_map::Entry::Occupied(entry) => (*entry.get()).clone().into(),
+        }
+    }
 }
 
 impl ggez::event::EventHandler for EventHandler {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> ggez::GameResult<()> {
-        if let Some(update) = &self.update {
-            let r = update.apply(&mut self.globals, vec![], None);
-            ordie(&mut self.globals, r);
-        }
+    fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        let dt = ggez::timer::duration_to_f64(ggez::timer::delta(ctx));
+        self.record_update(dt);
+        self.dispatch_update(dt);
+        self.sync_quit(ctx);
         Ok(())
     }
     fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
-        if let Some(draw) = &self.draw {
-            let r = draw.apply(&mut self.globals, vec![], None);
-            ordie(&mut self.globals, r);
+        if self.draw.is_some() {
+            self.call(self.draw.clone(), vec![]);
             ggez::graphics::present(ctx)?;
         }
+        self.sync_quit(ctx);
         std::thread::yield_now();
         Ok(())
     }
@@ -92,10 +269,9 @@ impl ggez::event::EventHandler for EventHandler {
         let btn = self.translate_button(btn);
         let x = (x as f64).into();
         let y = (y as f64).into();
-        if let Some(mouse_down) = &self.mouse_down {
-            let r = mouse_down.apply(&mut self.globals, vec![x, y, btn], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![x, y, btn];
+        self.record("mouse_down", &args);
+        self.call(self.mouse_down.clone(), args);
     }
     fn mouse_button_up_event(
         &mut self,
@@ -107,98 +283,195 @@ impl ggez::event::EventHandler for EventHandler {
         let btn = self.translate_button(btn);
         let x = (x as f64).into();
         let y = (y as f64).into();
-        if let Some(mouse_up) = &self.mouse_up {
-            let r = mouse_up.apply(&mut self.globals, vec![x, y, btn], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![x, y, btn];
+        self.record("mouse_up", &args);
+        self.call(self.mouse_up.clone(), args);
     }
     fn mouse_motion_event(&mut self, _ctx: &mut ggez::Context, x: f32, y: f32, dx: f32, dy: f32) {
         let x = (x as f64).into();
         let y = (y as f64).into();
         let dx = (dx as f64).into();
         let dy = (dy as f64).into();
-        if let Some(mouse_move) = &self.mouse_move {
-            let r = mouse_move.apply(&mut self.globals, vec![x, y, dx, dy], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![x, y, dx, dy];
+        self.record("mouse_move", &args);
+        self.call(self.mouse_move.clone(), args);
     }
     fn mouse_wheel_event(&mut self, _ctx: &mut ggez::Context, x: f32, y: f32) {
         let x = (x as f64).into();
         let y = (y as f64).into();
-        if let Some(mouse_wheel) = &self.mouse_wheel {
-            let r = mouse_wheel.apply(&mut self.globals, vec![x, y], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![x, y];
+        self.record("mouse_wheel", &args);
+        self.call(self.mouse_wheel.clone(), args);
     }
     fn key_down_event(
         &mut self,
         ctx: &mut ggez::Context,
         keycode: ggez::event::KeyCode,
-        _keymods: ggez::event::KeyMods,
+        keymods: ggez::event::KeyMods,
         repeat: bool,
     ) {
-        if keycode == ggez::event::KeyCode::Escape {
-            ggez::event::quit(ctx);
-            return;
-        }
         let key = self.translate_keycode(keycode);
-        if let Some(key_down) = &self.key_down {
-            let r = key_down.apply(&mut self.globals, vec![key.into(), repeat.into()], None);
-            ordie(&mut self.globals, r);
-        }
+        let mods = input::keymods_to_value(keymods);
+        self.record("key_down", &[key.clone(), mods.clone(), repeat.into()]);
+        self.dispatch_key_down(key, repeat, mods);
+        self.sync_quit(ctx);
     }
     fn key_up_event(
         &mut self,
         _ctx: &mut ggez::Context,
         keycode: ggez::event::KeyCode,
-        _keymods: ggez::event::KeyMods,
+        keymods: ggez::event::KeyMods,
     ) {
         let key = self.translate_keycode(keycode);
-        if let Some(key_up) = self.key_up.clone() {
-            let r = key_up.apply(&mut self.globals, vec![key.into()], None);
-            ordie(&mut self.globals, r);
-        }
+        let mods = input::keymods_to_value(keymods);
+        let args = vec![key, mods];
+        self.record("key_up", &args);
+        self.call(self.key_up.clone(), args);
     }
     fn text_input_event(&mut self, _ctx: &mut ggez::Context, ch: char) {
-        if let Some(text_input) = self.text_input.clone() {
-            let ch = Value::from(ch);
-            let r = text_input.apply(&mut self.globals, vec![ch], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![Value::from(ch)];
+        self.record("text_input", &args);
+        self.call(self.text_input.clone(), args);
     }
     fn resize_event(&mut self, _ctx: &mut ggez::Context, width: f32, height: f32) {
         let width = (width as f64).into();
         let height = (height as f64).into();
-        if let Some(resize) = self.resize.clone() {
-            let r = resize.apply(&mut self.globals, vec![width, height], None);
-            ordie(&mut self.globals, r);
-        }
+        let args = vec![width, height];
+        self.record("resize", &args);
+        self.call(self.resize.clone(), args);
+    }
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        btn: ggez::event::Button,
+        id: ggez::input::gamepad::GamepadId,
+    ) {
+        let id = input::gamepad_id_to_value(ctx, id);
+        let btn = self.translate_gamepad_button(btn);
+        let args = vec![id, btn];
+        self.record("gamepad_down", &args);
+        self.call(self.gamepad_down.clone(), args);
+    }
+    fn gamepad_button_up_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        btn: ggez::event::Button,
+        id: ggez::input::gamepad::GamepadId,
+    ) {
+        let id = input::gamepad_id_to_value(ctx, id);
+        let btn = self.translate_gamepad_button(btn);
+        let args = vec![id, btn];
+        self.record("gamepad_up", &args);
+        self.call(self.gamepad_up.clone(), args);
+    }
+    fn gamepad_axis_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        axis: ggez::event::Axis,
+        value: f32,
+        id: ggez::input::gamepad::GamepadId,
+    ) {
+        let id = input::gamepad_id_to_value(ctx, id);
+        let axis = self.translate_gamepad_axis(axis);
+        let value = (value as f64).into();
+        let args = vec![id, axis, value];
+        self.record("gamepad_axis", &args);
+        self.call(self.gamepad_axis.clone(), args);
+    }
+    fn quit_event(&mut self, _ctx: &mut ggez::Context) -> bool {
+        // returning true cancels the quit
+        self.record("quit", &[]);
+        !self.dispatch_quit()
     }
 }
 
 struct Stash {
     ctx: &'static mut ggez::Context,
     event_loop: Option<ggez::event::EventsLoop>,
+    paused: bool,
 }
 
 pub(super) fn new() -> NativeModule {
     NativeModule::new(NAME, |m| {
+        m.class::<CallbackError, _>("CallbackError", |cls| {
+            cls.doc("An error raised by a callback, as passed to the on_error callback of run");
+            cls.ifunc("message", [], "", |owner, _globals, _, _| {
+                Ok(owner.borrow().message.clone().into())
+            });
+            cls.ifunc(
+                "trace",
+                [],
+                "The mtots stack trace of the error, as a list of strings",
+                |owner, _globals, _, _| {
+                    Ok(owner
+                        .borrow()
+                        .trace
+                        .iter()
+                        .map(|mark| Value::from(mark.clone()))
+                        .collect::<Vec<_>>()
+                        .into())
+                },
+            );
+            cls.ifunc(
+                "describe",
+                [],
+                "The message and stack trace, as they would be reported if unhandled",
+                |owner, _globals, _, _| Ok(owner.borrow().description.clone().into()),
+            );
+        });
         m.func(
             "init",
             ArgSpec::builder()
                 .def("name", "")
                 .def("author", "")
-                .def("resource_paths", []),
-            "",
+                .def("resource_paths", [])
+                .def("mode", ())
+                .def("setup", ()),
+            concat!(
+                "Initializes the ggez context.\n",
+                "mode is a map of window mode options (size, width, height, ",
+                "fullscreen, maximized, borderless, resizable, min_size, max_size), ",
+                "and setup is a map of window setup options ",
+                "(title, vsync, samples, icon, srgb)",
+            ),
             |globals, args, _| {
                 let mut args = args.into_iter();
                 let name = args.next().unwrap().into_string()?;
                 let author = args.next().unwrap().into_string()?;
                 let resource_paths = Vec::<RcStr>::try_from(args.next().unwrap())?;
-                initggez(globals, name, author, resource_paths)?;
+                let mode = WindowMode::try_from(args.next().unwrap())?;
+                let setup = WindowSetup::try_from(args.next().unwrap())?;
+                initggez(globals, name, author, resource_paths, mode, setup)?;
                 Ok(Value::Nil)
             },
         );
+        m.func(
+            "quit",
+            [],
+            "Asks the event loop to exit at the end of the current frame",
+            |globals, _, _| {
+                match drawctx(globals)? {
+                    Some(ctx) => ggez::event::quit(ctx),
+                    None => set_stub_quit(globals),
+                }
+                Ok(Value::Nil)
+            },
+        );
+        m.func(
+            "set_paused",
+            ["paused"],
+            "While paused, the update callback is not called",
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let paused = args.next().unwrap().truthy();
+                drawctx(globals)?;
+                set_paused(globals, paused);
+                Ok(Value::Nil)
+            },
+        );
+        m.func("paused", [], "", |globals, _, _| {
+            Ok(is_paused(globals).into())
+        });
         m.func(
             "run",
             ArgSpec::builder()
@@ -212,8 +485,83 @@ pub(super) fn new() -> NativeModule {
                 .def("key_down", ())
                 .def("key_up", ())
                 .def("text_input", ())
-                .def("resize", ()),
-            "",
+                .def("resize", ())
+                .def("gamepad_down", ())
+                .def("gamepad_up", ())
+                .def("gamepad_axis", ())
+                .def("quit_requested", ())
+                .def("on_error", ())
+                .def("key_repeat", true)
+                .def("quit_on_escape", true)
+                .def("headless", ())
+                .def("record", ())
+                .def("replay", ()),
+            concat!(
+                "Runs the ggez event loop with the given callbacks.\n",
+                "update is called with the time since the last frame, in seconds.\n",
+                "key_down is called with (key, repeat, mods) and key_up with ",
+                "(key, mods), where mods is a map with 'shift', 'ctrl', 'alt' ",
+                "and 'logo' keys. If key_repeat is false, repeated key_down ",
+                "events are not delivered. mods comes last, after the arguments ",
+                "key_down and key_up used to take, but callbacks written for those ",
+                "(key, repeat) and (key) signatures must now accept it too, ",
+                "e.g. def(key, repeat, mods=nil). Keys are ggez key codes; ",
+                "scancodes are not available, as ggez 0.5 does not pass them ",
+                "to its event handlers.\n",
+                "gamepad_down and gamepad_up are called with (gamepad_id, button), ",
+                "and gamepad_axis with (gamepad_id, axis, value).\n",
+                "quit_requested is called when the window is asked to close ",
+                "(or Escape is pressed), and may return false to cancel quitting. ",
+                "If quit_on_escape is false, the Escape key is delivered ",
+                "like any other key instead of quitting.\n",
+                "on_error is called with a CallbackError for any error raised by ",
+                "a callback (with message, trace and describe methods), and may return 'continue' (or nil), 'pause' ",
+                "(stop calling update until set_paused(false)) or 'quit'. ",
+                "Without on_error, the event loop stops, ggez is shut down, and the ",
+                "error is then reported with its stack trace, like any other ",
+                "uncaught error.\n",
+                "headless is an optional map of options for running without a human ",
+                "at the keyboard (e.g. in tests), with keys:\n",
+                "  frames: the number of frames to run for (required)\n",
+                "  dt: the delta passed to update each frame (default 1/60), ",
+                "or a list of deltas, one for each frame\n",
+                "  events: a list of scripted events, each of the form ",
+                "[frame, kind, ...args], delivered before the update of that frame. ",
+                "kind and args are one of\n",
+                "    'key_down', key, mods=nil, repeat=false\n",
+                "    'key_up', key, mods=nil\n",
+                "    'text_input', ch\n",
+                "    'mouse_down', x, y, button='Left'\n",
+                "    'mouse_up', x, y, button='Left'\n",
+                "    'mouse_move', x, y, dx=0, dy=0\n",
+                "    'mouse_wheel', x, y\n",
+                "    'resize', width, height\n",
+                "    'gamepad_down', gamepad_id, button\n",
+                "    'gamepad_up', gamepad_id, button\n",
+                "    'gamepad_axis', gamepad_id, axis, value\n",
+                "    'quit'\n",
+                "  render: whether to actually render frames, which requires a display ",
+                "(default false). Without rendering, drawing calls do nothing, and ",
+                "anything else that needs a ggez context (e.g. loading images) fails\n",
+                "  capture: a directory to save rendered frames to, as PNG files ",
+                "(requires render)\n",
+                "  capture_every: save every n-th frame (default 1)\n",
+                "  size: the [width, height] of the screen when not rendering ",
+                "(default [800, 600])\n",
+                "record is an optional path of a file to record all input events ",
+                "and update deltas to, with their frame numbers and timestamps.\n",
+                "replay is an optional path of such a recording, whose events are ",
+                "fed to the callbacks instead of live input, with the recorded deltas. ",
+                "Replaying runs headless with rendering, and may be combined with ",
+                "headless options (other than frames, dt and events), e.g. ",
+                "headless=['render': false] to replay without a display. ",
+                "Together with a seeded a.rand.Rng, this reproduces a session exactly, ",
+                "unless the callbacks depend on e.g. the wall clock.\n",
+                "An error that is not handled by on_error stops the event loop in ",
+                "the same way whether or not running headless: ggez is shut down, and ",
+                "the error is reported like any other uncaught error, so a failing ",
+                "headless run fails the script",
+            ),
             |globals, args, _| {
                 let mut args = args.into_iter();
                 let init = getornil(args.next().unwrap());
@@ -227,31 +575,55 @@ pub(super) fn new() -> NativeModule {
                 let key_up = getornil(args.next().unwrap());
                 let text_input = getornil(args.next().unwrap());
                 let resize = getornil(args.next().unwrap());
+                let gamepad_down = getornil(args.next().unwrap());
+                let gamepad_up = getornil(args.next().unwrap());
+                let gamepad_axis = getornil(args.next().unwrap());
+                let quit_requested = getornil(args.next().unwrap());
+                let on_error = getornil(args.next().unwrap());
+                let key_repeat = args.next().unwrap().truthy();
+                let quit_on_escape = args.next().unwrap().truthy();
+                let headless = args.next().unwrap();
+                let record = match args.next().unwrap() {
+                    Value::Nil => None,
+                    path => Some(path.into_string()?),
+                };
+                let headless = match args.next().unwrap() {
+                    Value::Nil if headless.is_nil() => None,
+                    Value::Nil => Some(Headless::try_from(headless)?),
+                    path => {
+                        let recording = Recording::load(path.into_string()?.str())?;
+                        Some(Headless::replay(recording, headless)?)
+                    }
+                };
+                let recorder = match record {
+                    Some(path) => Some(Recorder::new(path.str())?),
+                    None => None,
+                };
                 globals.request_trampoline(move |mut globals| {
+                    if let Some(headless) = &headless {
+                        if !headless.render {
+                            // Without rendering, no ggez context needs to be created,
+                            // and drawing calls do nothing
+                            let r = init_stub(&mut globals, headless);
+                            ordie(&mut globals, r);
+                        }
+                    }
+
                     // We initialize with defaults if 'init' was not called
                     // before run. However, to actually configure these
                     // values, 'init' will need to be called explicitly
-                    if !globals.stash().has::<Stash>() {
-                        let r = initggez(&mut globals, "".into(), "".into(), vec![]);
+                    if !globals.stash().has::<Stash>() && !is_stubbed(&globals) {
+                        let r = initggez(
+                            &mut globals,
+                            "".into(),
+                            "".into(),
+                            vec![],
+                            ggez::conf::WindowMode::default().into(),
+                            ggez::conf::WindowSetup::default().into(),
+                        );
                         ordie(&mut globals, r);
                     };
 
-                    let (ctx, mut event_loop) = {
-                        let mut stash = globals.stash_mut().get_mut::<Stash>().unwrap();
-                        let event_loop = std::mem::replace(&mut stash.event_loop, None)
-                            .expect("Event loop has already been used");
-                        (
-                            // kinda yucky to use unsafe here,
-                            // but it would be quite a bit of work to avoid this
-                            unsafe {
-                                std::mem::transmute::<&mut ggez::Context, &mut ggez::Context>(
-                                    &mut stash.ctx,
-                                )
-                            },
-                            event_loop,
-                        )
-                    };
-
                     if let Some(init) = init {
                         let r = init.apply(&mut globals, vec![], None);
                         ordie(&mut globals, r);
@@ -269,15 +641,53 @@ pub(super) fn new() -> NativeModule {
                         key_up,
                         text_input,
                         resize,
+                        gamepad_down,
+                        gamepad_up,
+                        gamepad_axis,
+                        quit_requested,
+                        on_error,
+                        key_repeat,
+                        quit_on_escape,
+                        quit: false,
+                        error: None,
+                        recorder,
                         keycode_map: HashMap::new(),
                         mouse_button_map: HashMap::new(),
+                        gamepad_button_map: HashMap::new(),
+                        gamepad_axis_map: HashMap::new(),
+                    };
+
+                    if let Some(headless) = headless {
+                        run_headless(&mut event_handler, headless);
+                        event_handler.shutdown();
+                        return;
+                    }
+
+                    let (ctx, mut event_loop) = {
+                        let mut stash = event_handler
+                            .globals
+                            .stash_mut()
+                            .get_mut::<Stash>()
+                            .unwrap();
+                        let event_loop = std::mem::replace(&mut stash.event_loop, None)
+                            .expect("Event loop has already been used");
+                        (
+                            // kinda yucky to use unsafe here,
+                            // but it would be quite a bit of work to avoid this
+                            unsafe {
+                                std::mem::transmute::<&mut ggez::Context, &mut ggez::Context>(
+                                    &mut stash.ctx,
+                                )
+                            },
+                            event_loop,
+                        )
                     };
 
                     match ggez::event::run(ctx, &mut event_loop, &mut event_handler) {
                         Ok(_) => {}
                         Err(e) => eprintln!("ggez error: {:?}", e),
                     }
-                    deinitggez(event_handler.globals);
+                    event_handler.shutdown();
                 })
             },
         );
@@ -289,8 +699,12 @@ fn initggez(
     name: RcStr,
     author: RcStr,
     resource_paths: Vec<RcStr>,
+    mode: WindowMode,
+    setup: WindowSetup,
 ) -> Result<()> {
-    let mut builder = ggez::ContextBuilder::new(name.str(), author.str());
+    let mut builder = ggez::ContextBuilder::new(name.str(), author.str())
+        .window_mode(mode.into())
+        .window_setup(setup.into());
     for resource_path in resource_paths {
         builder = builder.add_resource_path(resource_path.str());
     }
@@ -300,20 +714,33 @@ fn initggez(
     globals.stash_mut().set(Stash {
         ctx,
         event_loop: Some(event_loop),
+        paused: false,
     })?;
 
     Ok(())
 }
 
-fn deinitggez(mut globals: Globals) {
-    let stash = globals.stash_mut().remove::<Stash>();
-    unsafe { Box::from_raw(stash.ctx) };
+fn deinitggez(globals: &mut Globals) {
+    if globals.stash().has::<Stash>() {
+        let stash = globals.stash_mut().remove::<Stash>();
+        unsafe { Box::from_raw(stash.ctx) };
+    }
+    // So that a later run starts over, whether or not it's headless
+    if globals.stash().has::<Stub>() {
+        globals.stash_mut().remove::<Stub>();
+    }
 }
 
 fn getctx(globals: &mut Globals) -> Result<&'static mut ggez::Context> {
     use std::ops::DerefMut;
     let stash = globals.stash_mut();
     if !stash.has::<Stash>() {
+        if stash.has::<Stub>() {
+            return Err(rterr!(
+                "This requires a GGEZ context, which is not available when running \
+                 headless without rendering"
+            ));
+        }
         return Err(rterr!("GGEZ context used before being initialized"));
     }
     let mut stash = stash.get_mut::<Stash>()?;
@@ -322,6 +749,34 @@ fn getctx(globals: &mut Globals) -> Result<&'static mut ggez::Context> {
     Ok(unsafe { std::mem::transmute::<&mut ggez::Context, _>(stash.ctx) })
 }
 
+/// Like getctx, but returns None when running headless without rendering,
+/// in which case drawing calls should do nothing
+fn drawctx(globals: &mut Globals) -> Result<Option<&'static mut ggez::Context>> {
+    if is_stubbed(globals) {
+        Ok(None)
+    } else {
+        getctx(globals).map(Some)
+    }
+}
+
+fn is_paused(globals: &Globals) -> bool {
+    let stash = globals.stash();
+    if stash.has::<Stub>() {
+        return stash.get::<Stub>().unwrap().paused;
+    }
+    stash.has::<Stash>() && stash.get::<Stash>().unwrap().paused
+}
+
+fn set_paused(globals: &mut Globals, paused: bool) {
+    let stash = globals.stash_mut();
+    if stash.has::<Stub>() {
+        stash.get_mut::<Stub>().unwrap().paused = paused;
+    }
+    if stash.has::<Stash>() {
+        stash.get_mut::<Stash>().unwrap().paused = paused;
+    }
+}
+
 fn getornil(value: Value) -> Option<Value> {
     match value {
         Value::Nil => None,
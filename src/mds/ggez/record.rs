@@ -0,0 +1,166 @@
+//! Recording the events delivered to the event loop, and replaying them
+//!
+//! A recording is a text file with one JSON list per line, of the form
+//! [frame, time, kind, ...args], where time is the number of seconds since
+//! the event loop started. kind is either 'update' (with the delta passed to
+//! update as its only argument), or any of the scripted event kinds accepted
+//! in headless mode, so that a recording can be replayed by converting it
+//! into headless options
+use super::*;
+use crate::mds::json;
+use crate::Key;
+use crate::Map;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+pub(super) struct Recorder {
+    out: BufWriter<std::fs::File>,
+    start: Instant,
+    frame: usize,
+}
+
+impl Recorder {
+    pub(super) fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            start: Instant::now(),
+            frame: 0,
+        })
+    }
+    fn write(&mut self, kind: &str, args: &[Value]) -> Result<()> {
+        let mut line = vec![
+            serde_json::Value::from(self.frame),
+            serde_json::Value::from(self.start.elapsed().as_secs_f64()),
+            serde_json::Value::from(kind),
+        ];
+        for arg in args {
+            line.push(json::to_serde(arg.clone())?);
+        }
+        writeln!(self.out, "{}", serde_json::Value::Array(line))?;
+        Ok(())
+    }
+    /// Records an input event, delivered before the update of the current frame
+    pub(super) fn event(&mut self, kind: &str, args: &[Value]) -> Result<()> {
+        self.write(kind, args)
+    }
+    /// Records the delta of the current frame, and moves on to the next one
+    pub(super) fn update(&mut self, dt: f64) -> Result<()> {
+        self.write("update", &[dt.into()])?;
+        self.frame += 1;
+        Ok(())
+    }
+    pub(super) fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// The updates and events of a recording
+pub struct Recording {
+    /// The delta of each recorded frame
+    pub dts: Vec<f64>,
+    /// The recorded events, as [frame, kind, ...args] lists
+    pub events: Vec<Value>,
+}
+
+impl Recording {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let mut dts = Vec::new();
+        let mut events = Vec::new();
+        for (lineno, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: serde_json::Value = match serde_json::from_str(line) {
+                Ok(line) => line,
+                Err(error) => {
+                    return Err(rterr!(
+                        "{}:{}: invalid recorded event: {}",
+                        path.display(),
+                        lineno + 1,
+                        error
+                    ))
+                }
+            };
+            let mut parts: Vec<Value> = match json::from_serde(line)? {
+                Value::List(list) => list.borrow().iter().cloned().collect(),
+                value => {
+                    return Err(rterr!(
+                        "{}:{}: expected a list, but got {:?}",
+                        path.display(),
+                        lineno + 1,
+                        value
+                    ))
+                }
+            };
+            if parts.len() < 3 {
+                return Err(rterr!(
+                    "{}:{}: recorded events need at least a frame, time and kind",
+                    path.display(),
+                    lineno + 1
+                ));
+            }
+            // The time is only informational
+            parts.remove(1);
+            let frame = parts[0].usize()?;
+            match &parts[1] {
+                Value::String(kind) if kind.str() == "update" => {
+                    if frame != dts.len() || parts.len() != 3 {
+                        return Err(rterr!(
+                            "{}:{}: malformed update for frame {}",
+                            path.display(),
+                            lineno + 1,
+                            frame
+                        ));
+                    }
+                    dts.push(parts[2].f64()?);
+                }
+                _ => events.push(Value::from(parts)),
+            }
+        }
+        Ok(Self { dts, events })
+    }
+}
+
+impl From<Recording> for Value {
+    fn from(recording: Recording) -> Self {
+        let dts: Vec<Value> = recording.dts.into_iter().map(Value::from).collect();
+        vec![
+            ("frames", Value::from(dts.len())),
+            ("dt", Value::from(dts)),
+            ("events", Value::from(recording.events)),
+        ]
+        .into_iter()
+        .map(|(k, v)| (Key::from(k.to_owned()), v))
+        .collect::<Map>()
+        .into()
+    }
+}
+
+impl EventHandler {
+    /// Records the given event, if recording
+    pub(super) fn record(&mut self, kind: &str, args: &[Value]) {
+        if let Some(recorder) = &mut self.recorder {
+            let r = recorder.event(kind, args);
+            self.check(r);
+        }
+    }
+    pub(super) fn record_update(&mut self, dt: f64) {
+        if let Some(recorder) = &mut self.recorder {
+            let r = recorder.update(dt);
+            self.check(r);
+        }
+    }
+    pub(super) fn finish_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(error) = recorder.finish() {
+                eprintln!("Failed to save recording: {:?}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Frame timing. When running headless, these follow the scripted deltas
+//! of a.ggez.headless instead of the wall clock
+use super::*;
+
+pub const NAME: &str = "a.ggez.timer";
+
+pub(super) fn new() -> NativeModule {
+    NativeModule::new(NAME, |m| {
+        m.func(
+            "fps",
+            [],
+            "Returns the average frames per second over the last few frames",
+            |globals, _, _| {
+                if let Some(average) = with_headless_state(globals, |c| c.average_delta()) {
+                    return Ok(if average > 0.0 { 1.0 / average } else { 0.0 }.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::fps(ctx).into())
+            },
+        );
+        m.func(
+            "delta",
+            [],
+            "Returns the time since the last frame, in seconds",
+            |globals, _, _| {
+                if let Some(delta) = with_headless_state(globals, |c| c.delta) {
+                    return Ok(delta.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::duration_to_f64(ggez::timer::delta(ctx)).into())
+            },
+        );
+        m.func(
+            "average_delta",
+            [],
+            "Returns the average time per frame over the last few frames, in seconds",
+            |globals, _, _| {
+                if let Some(average) = with_headless_state(globals, |c| c.average_delta()) {
+                    return Ok(average.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::duration_to_f64(ggez::timer::average_delta(ctx)).into())
+            },
+        );
+        m.func(
+            "time_since_start",
+            [],
+            "Returns the time since the ggez context was created, in seconds",
+            |globals, _, _| {
+                if let Some(elapsed) = with_headless_state(globals, |c| c.elapsed) {
+                    return Ok(elapsed.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::duration_to_f64(ggez::timer::time_since_start(ctx)).into())
+            },
+        );
+        m.func(
+            "ticks",
+            [],
+            "Returns the number of frames that have passed so far",
+            |globals, _, _| {
+                if let Some(ticks) = with_headless_state(globals, |c| c.ticks) {
+                    return Ok(ticks.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::ticks(ctx).into())
+            },
+        );
+        m.func(
+            "check_update_time",
+            ["target_fps"],
+            concat!(
+                "Fixed timestep helper. Returns true if enough time has accumulated ",
+                "for another update at target_fps updates per second. Intended to be ",
+                "called in a loop inside update, e.g.\n",
+                "    while timer.check_update_time(60) { step(1 / 60) }",
+            ),
+            |globals, args, _| {
+                let mut args = args.into_iter();
+                let target_fps = args.next().unwrap().usize()?;
+                if target_fps == 0 {
+                    return Err(rterr!("target_fps must be positive"));
+                }
+                let target_dt = 1.0 / target_fps as f64;
+                let check = with_headless_state(globals, |c| {
+                    if c.residual > target_dt {
+                        c.residual -= target_dt;
+                        true
+                    } else {
+                        false
+                    }
+                });
+                if let Some(check) = check {
+                    return Ok(check.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::check_update_time(ctx, target_fps as u32).into())
+            },
+        );
+        m.func(
+            "remaining_update_time",
+            [],
+            concat!(
+                "Returns the fractional time left over after calls to ",
+                "check_update_time, in seconds. Useful for interpolating when drawing",
+            ),
+            |globals, _, _| {
+                if let Some(residual) = with_headless_state(globals, |c| c.residual) {
+                    return Ok(residual.into());
+                }
+                let ctx = getctx(globals)?;
+                Ok(ggez::timer::duration_to_f64(ggez::timer::remaining_update_time(ctx)).into())
+            },
+        );
+    })
+}
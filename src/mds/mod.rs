@@ -1,9 +1,14 @@
 use crate::Globals;
 
+#[cfg(feature = "audio")]
+mod audio;
+
+mod color;
+
 #[cfg(feature = "gamekit")]
 mod ggez;
 
-#[cfg(feature = "basekit")]
+#[cfg(any(feature = "basekit", feature = "gamekit"))]
 mod json;
 
 #[cfg(feature = "basekit")]
@@ -16,9 +21,20 @@ mod regex;
 mod webview;
 
 pub fn add_standard_modules(_globals: &mut Globals) {
-    #[cfg(feature = "basekit")]
+    _globals.add_native_module(color::new()).unwrap();
+
+    #[cfg(feature = "audio")]
+    {
+        _globals.add_native_module(audio::new()).unwrap();
+    }
+
+    #[cfg(any(feature = "basekit", feature = "gamekit"))]
     {
         _globals.add_native_module(json::new()).unwrap();
+    }
+
+    #[cfg(feature = "basekit")]
+    {
         _globals.add_native_module(rand::new()).unwrap();
         _globals.add_native_module(regex::new()).unwrap();
     }